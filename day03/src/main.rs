@@ -1,92 +1,16 @@
-use std::collections::{HashMap, HashSet};
+mod wires;
 
-type Point = (i64, i64);
+use std::env;
 
-fn manhattan_distance(p1: &Point, p2: &Point) -> usize {
-  ((p1.0 - p2.0).abs() + (p1.1 - p2.1).abs()) as usize
-}
+use wires::{common_crossings, crossings, svg, Wire};
 
-#[test]
-fn test_manhattan_distance() {
-  assert_eq!(manhattan_distance(&(2, 3), &(3, -2)), 1 + 5);
-}
-
-fn minimize_manhattan(points1: &HashSet<Point>, points2: &HashSet<Point>) -> usize {
-  let intersections = points1.intersection(&points2);
-  let start = (0, 0);
-  let mut min_d = std::usize::MAX;
-  for p in intersections {
-    //println!("Intersection at {:?}", &p);
-    let d = manhattan_distance(&start, &p);
-    if d < min_d {
-      min_d = d;
-    }
-  }
-  min_d
-}
-
-fn calc_points(start: &Point, instructions: &str) -> (HashSet<Point>, HashMap<Point, usize>) {
-  let mut visited = HashSet::new();
-  let mut distances = HashMap::new();
-
-  let mut iter = instructions.split(",");
-  let mut x = start.0;
-  let mut y = start.1;
-  let mut cur_dist: usize = 0;
-  while let Some(op) = iter.next() {
-    let val: usize = op[1..].parse::<usize>().expect("unable to parse number");
-    match op.bytes().nth(0).expect("no byte read") {
-      b'R' => {
-        for i in 1..val + 1 {
-          //println!("Going RIGHT: {}, cur_dist: {}", val, cur_dist);
-          cur_dist += 1;
-          let p = (x + (i as i64), y);
-          visited.insert(p.clone());
-          distances.insert(p, cur_dist);
-        }
-        x += val as i64;
-      }
-      b'L' => {
-        for i in 1..val + 1 {
-          //println!("Going LEFT: {}, cur_dist: {}", val, cur_dist);
-          cur_dist += 1;
-          let p = (x - (i as i64), y);
-          visited.insert(p.clone());
-          distances.insert(p, cur_dist);
-        }
-        x -= val as i64;
-      }
-      b'U' => {
-        for i in 1..val + 1 {
-          //println!("Going UP: {}, cur_dist: {}", val, cur_dist);
-          cur_dist += 1;
-          let p = (x, y + (i as i64));
-          visited.insert(p.clone());
-          distances.insert(p, cur_dist);
-        }
-        y += val as i64;
-      }
-      b'D' => {
-        for i in 1..val + 1 {
-          //println!("Going DOWN: {}, cur_dist: {}", val, cur_dist);
-          cur_dist += 1;
-          let p = (x, y - (i as i64));
-          visited.insert(p.clone());
-          distances.insert(p, cur_dist);
-        }
-        y -= val as i64;
-      }
-      _ => panic!("Unsupported direction"),
-    }
-  }
-  return (visited, distances);
+fn parse_wires(wire1: &str, wire2: &str) -> Vec<Wire> {
+  vec![Wire::parse(wire1).unwrap(), Wire::parse(wire2).unwrap()]
 }
 
 fn part_one(wire1: &str, wire2: &str) -> usize {
-  let start = (0, 0);
-  let (points1, _distances1) = calc_points(&start, wire1);
-  let (points2, _distances2) = calc_points(&start, wire2);
-  minimize_manhattan(&points1, &points2)
+  let wires = parse_wires(wire1, wire2);
+  crossings(&wires).iter().map(|c| c.distance()).min().unwrap_or(usize::MAX)
 }
 
 #[test]
@@ -106,20 +30,8 @@ fn test_part_one() {
 }
 
 fn part_two(wire1: &str, wire2: &str) -> usize {
-  let start = (0, 0);
-  let (points1, distances1) = calc_points(&start, wire1);
-  let (points2, distances2) = calc_points(&start, wire2);
-  let intersections = points1.intersection(&points2);
-
-  let mut min_d = std::usize::MAX;
-  for p in intersections {
-    let d = distances1.get(p).expect("point must exist because it is an intersection")
-      + distances2.get(p).expect("point must exist because it is an intersection");
-    if d < min_d {
-      min_d = d;
-    }
-  }
-  min_d
+  let wires = parse_wires(wire1, wire2);
+  crossings(&wires).iter().map(|c| c.combined_steps()).min().unwrap_or(usize::MAX)
 }
 
 #[test]
@@ -143,6 +55,12 @@ fn main() {
 
   let wire2= "L1005,D527,R864,D622,R482,D647,R29,U459,R430,D942,R550,D163,L898,U890,L271,D216,L52,U731,R715,U925,L614,U19,R687,D832,L381,U192,L293,D946,L642,D2,L124,U66,R492,U281,R181,U624,R294,U767,R443,U424,R241,D225,R432,D419,L647,U290,L647,D985,L694,D777,L382,D231,R809,D467,L917,D217,R422,U490,L873,D537,R176,U856,L944,D875,L485,D49,R333,D220,L354,U789,R256,D73,R905,U146,R798,D429,R111,D585,L275,D471,R220,D619,L680,U757,R580,U497,L620,U753,R58,U574,L882,U484,R297,D899,L95,D186,R619,D622,R65,U714,L402,U950,R647,D60,L659,U101,L917,D736,L531,U398,R26,U134,R837,U294,R364,D55,R254,D999,R868,U978,R434,U661,R362,D158,L50,D576,L146,D249,L562,D433,R206,D376,L650,U285,L427,D406,L526,D597,R557,U554,L463,D157,L811,U961,R648,D184,L962,U695,R138,U661,L999,U806,L413,U54,L865,U931,L319,U235,L794,D12,L456,D918,L456,U214,L739,D772,R90,D478,R23,D658,R919,D990,L307,D534,L40,D324,L4,U805,L605,U534,R727,U452,R733,D416,L451,U598,R215,D545,L563,D222,L295,D669,R706,U11,R44,D392,L518,D437,L634,U874,L641,U240,L11,D279,L153,U601,L238,U924,L292,D406,L360,D203,R874,D506,R806,U9,R713,D891,L587,U538,L867,D637,R889,U186,R728,D672,R573,U461,R222,D703,R178,U336,L896,D924,L445,D365,L648,U3,L734,U959,R344,U314,R331,D929,L364,D937,L896,D191,R218,U256,L975,D506,R510,D392,R878,U896,L177,U4,R516,D873,R57,D530,R140,D827,L263,U848,L88,U309,L801,U670,R874,D358,L49,D259,L188,U419,R705,D498,R496,U576,R808,D959,L861,U437,L618,D112,R725,D546,R338,U879,R522,U892,R230,D367,R901,D737,L942,D689,R976,D369,R157";
 
+  if env::args().nth(1).as_deref() == Some("svg") {
+    let wires = parse_wires(wire1, wire2);
+    print!("{}", svg(&wires, &common_crossings(&wires)));
+    return;
+  }
+
   let solution1 = part_one(&wire1, &wire2);
   println!("Solution 1: {}", solution1);
 
@@ -0,0 +1,317 @@
+use std::collections::{BTreeMap, HashMap};
+
+pub type Point = (i64, i64);
+
+const ORIGIN: Point = (0, 0);
+
+pub fn manhattan_distance(p1: &Point, p2: &Point) -> usize {
+  ((p1.0 - p2.0).abs() + (p1.1 - p2.1).abs()) as usize
+}
+
+#[test]
+fn test_manhattan_distance() {
+  assert_eq!(manhattan_distance(&(2, 3), &(3, -2)), 1 + 5);
+}
+
+/// A straight piece of wire, either horizontal or vertical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+  pub start: Point,
+  pub end: Point,
+  /// steps along the wire before this segment starts
+  pub steps: usize,
+}
+
+impl Segment {
+  fn is_horizontal(&self) -> bool {
+    self.start.1 == self.end.1
+  }
+
+  /// The constant coordinate: y for horizontal segments, x for vertical ones.
+  fn line(&self) -> i64 {
+    if self.is_horizontal() {
+      self.start.1
+    } else {
+      self.start.0
+    }
+  }
+
+  /// The range covered along the line, smallest first.
+  fn span(&self) -> (i64, i64) {
+    let (a, b) = if self.is_horizontal() {
+      (self.start.0, self.end.0)
+    } else {
+      (self.start.1, self.end.1)
+    };
+    (a.min(b), a.max(b))
+  }
+
+  /// Steps along the wire to reach `p`, which must lie on this segment.
+  fn steps_to(&self, p: Point) -> usize {
+    self.steps + manhattan_distance(&self.start, &p)
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Wire {
+  pub segments: Vec<Segment>,
+}
+
+impl Wire {
+  /// Parses moves like `R75,D30,U83,L12`, starting at the origin.
+  pub fn parse(s: &str) -> Result<Wire, String> {
+    let mut segments = Vec::new();
+    let mut start = ORIGIN;
+    let mut steps = 0;
+    for op in s.trim().split(',') {
+      // only digits: no sign, the direction says which way to go
+      let len: u64 = op
+        .get(1..)
+        .filter(|n| n.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| format!("Invalid move: {:?}", op))?;
+      let len = len as i64;
+      let end = match op.as_bytes()[0] {
+        b'R' => (start.0 + len, start.1),
+        b'L' => (start.0 - len, start.1),
+        b'U' => (start.0, start.1 + len),
+        b'D' => (start.0, start.1 - len),
+        _ => return Err(format!("Unsupported direction: {:?}", op)),
+      };
+      segments.push(Segment { start, end, steps });
+      steps += len as usize;
+      start = end;
+    }
+    Ok(Wire { segments })
+  }
+
+  /// All corners of the wire, starting at the origin.
+  fn corners(&self) -> impl Iterator<Item = Point> + '_ {
+    std::iter::once(ORIGIN).chain(self.segments.iter().map(|s| s.end))
+  }
+}
+
+/// A point where two or more wires meet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crossing {
+  pub point: Point,
+  /// wire index -> fewest steps that wire needs to reach the point
+  pub steps: BTreeMap<usize, usize>,
+}
+
+impl Crossing {
+  pub fn distance(&self) -> usize {
+    manhattan_distance(&ORIGIN, &self.point)
+  }
+
+  pub fn combined_steps(&self) -> usize {
+    self.steps.values().sum()
+  }
+}
+
+/// Every point (except the origin) where at least two of the `wires` meet, ordered by point.
+///
+/// Wires crossing each other are found with a sweep line over x. Where two wires run along
+/// each other, only the ends of the shared stretch and its point closest to the origin are
+/// reported: the distance and the combined steps are smallest at one of those.
+pub fn crossings(wires: &[Wire]) -> Vec<Crossing> {
+  let mut hits: HashMap<Point, BTreeMap<usize, usize>> = HashMap::new();
+  let mut hit = |p: Point, wire: usize, segment: &Segment| {
+    if p != ORIGIN {
+      let steps = hits.entry(p).or_default().entry(wire).or_insert(usize::MAX);
+      *steps = (*steps).min(segment.steps_to(p));
+    }
+  };
+
+  let segments: Vec<(usize, &Segment)> = wires
+    .iter()
+    .enumerate()
+    .flat_map(|(i, wire)| wire.segments.iter().map(move |s| (i, s)))
+    .collect();
+
+  // at the same x, horizontal segments are added before and removed after vertical ones
+  // are checked, so touching ends count as well
+  const ADD: u8 = 0;
+  const CHECK: u8 = 1;
+  const REMOVE: u8 = 2;
+  let mut events = Vec::new();
+  for (i, &(_, segment)) in segments.iter().enumerate() {
+    if segment.is_horizontal() {
+      let (x0, x1) = segment.span();
+      events.push((x0, ADD, i));
+      events.push((x1, REMOVE, i));
+    } else {
+      events.push((segment.start.0, CHECK, i));
+    }
+  }
+  events.sort();
+
+  // horizontal segments crossing the sweep line, by y
+  let mut active: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+  for (x, kind, i) in events {
+    let (wire, segment) = segments[i];
+    match kind {
+      ADD => active.entry(segment.line()).or_default().push(i),
+      REMOVE => active.get_mut(&segment.line()).unwrap().retain(|&j| j != i),
+      _ => {
+        let (y0, y1) = segment.span();
+        for (&y, horizontal) in active.range(y0..=y1) {
+          for &j in horizontal {
+            let (other_wire, other) = segments[j];
+            if other_wire != wire {
+              hit((x, y), wire, segment);
+              hit((x, y), other_wire, other);
+            }
+          }
+        }
+      }
+    }
+  }
+
+  // wires running along each other on the same line
+  let mut lines: HashMap<(bool, i64), Vec<usize>> = HashMap::new();
+  for (i, &(_, segment)) in segments.iter().enumerate() {
+    lines.entry((segment.is_horizontal(), segment.line())).or_default().push(i);
+  }
+  for ((horizontal, line), on_line) in lines {
+    let point = |t: i64| if horizontal { (t, line) } else { (line, t) };
+    for (k, &i) in on_line.iter().enumerate() {
+      for &j in &on_line[k + 1..] {
+        let ((wire_a, a), (wire_b, b)) = (segments[i], segments[j]);
+        let (a0, a1) = a.span();
+        let (b0, b1) = b.span();
+        let (lo, hi) = (a0.max(b0), a1.min(b1));
+        if wire_a == wire_b || lo > hi {
+          continue;
+        }
+        for &t in &[lo, hi, 0.max(lo).min(hi)] {
+          hit(point(t), wire_a, a);
+          hit(point(t), wire_b, b);
+        }
+      }
+    }
+  }
+
+  let mut result: Vec<Crossing> = hits
+    .into_iter()
+    .filter(|(_, steps)| steps.len() >= 2)
+    .map(|(point, steps)| Crossing { point, steps })
+    .collect();
+  result.sort_by_key(|crossing| crossing.point);
+  result
+}
+
+/// The points every one of the `wires` passes through.
+pub fn common_crossings(wires: &[Wire]) -> Vec<Crossing> {
+  crossings(wires)
+    .into_iter()
+    .filter(|crossing| crossing.steps.len() == wires.len())
+    .collect()
+}
+
+/// The wires as SVG polylines, with a dot on every crossing. Up is up, as in the input.
+pub fn svg(wires: &[Wire], crossings: &[Crossing]) -> String {
+  const COLORS: [&str; 6] = ["#d62728", "#1f77b4", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b"];
+
+  let corners: Vec<Point> = wires.iter().flat_map(|wire| wire.corners()).collect();
+  let min_x = corners.iter().map(|p| p.0).min().unwrap_or(0);
+  let max_x = corners.iter().map(|p| p.0).max().unwrap_or(0);
+  let min_y = corners.iter().map(|p| p.1).min().unwrap_or(0);
+  let max_y = corners.iter().map(|p| p.1).max().unwrap_or(0);
+  let size = (max_x - min_x).max(max_y - min_y).max(1);
+  let margin = size / 20 + 1;
+  let stroke = size as f64 / 500.0;
+
+  let mut result = format!(
+    "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+    min_x - margin,
+    -max_y - margin,
+    max_x - min_x + 2 * margin,
+    max_y - min_y + 2 * margin
+  );
+  for (i, wire) in wires.iter().enumerate() {
+    let points: Vec<String> = wire.corners().map(|p| format!("{},{}", p.0, -p.1)).collect();
+    result.push_str(&format!(
+      "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+      points.join(" "),
+      COLORS[i % COLORS.len()],
+      stroke
+    ));
+  }
+  for crossing in crossings {
+    result.push_str(&format!(
+      "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"black\"/>\n",
+      crossing.point.0,
+      -crossing.point.1,
+      3.0 * stroke
+    ));
+  }
+  result.push_str(&format!(
+    "  <circle cx=\"0\" cy=\"0\" r=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"{}\"/>\n",
+    5.0 * stroke,
+    stroke
+  ));
+  result.push_str("</svg>\n");
+  result
+}
+
+#[cfg(test)]
+fn wires(s: &[&str]) -> Vec<Wire> {
+  s.iter().map(|w| Wire::parse(w).unwrap()).collect()
+}
+
+#[test]
+fn test_parse() {
+  let wire = Wire::parse("R8,U5,L5,D3").unwrap();
+  assert_eq!(
+    Segment {
+      start: (8, 0),
+      end: (8, 5),
+      steps: 8
+    },
+    wire.segments[1]
+  );
+  assert_eq!(Err("Unsupported direction: \"X3\"".to_string()), Wire::parse("R8,X3").map(|_| ()));
+  assert_eq!(Err("Invalid move: \"R\"".to_string()), Wire::parse("R").map(|_| ()));
+  assert_eq!(Err("Invalid move: \"R-5\"".to_string()), Wire::parse("R-5").map(|_| ()));
+  assert_eq!(Err("Invalid move: \"U+5\"".to_string()), Wire::parse("U+5").map(|_| ()));
+}
+
+#[test]
+fn test_crossings() {
+  let found = crossings(&wires(&["R8,U5,L5,D3", "U7,R6,D4,L4"]));
+  let points: Vec<Point> = found.iter().map(|c| c.point).collect();
+  assert_eq!(vec![(3, 3), (6, 5)], points);
+  assert_eq!(vec![6, 11], found.iter().map(|c| c.distance()).collect::<Vec<_>>());
+  assert_eq!(vec![40, 30], found.iter().map(|c| c.combined_steps()).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_overlapping_wires() {
+  // the wires share the stretch from (2, 0) to (5, 0), in opposite directions
+  let found = crossings(&wires(&["R5,U1", "U2,R7,D2,L5"]));
+  let points: Vec<Point> = found.iter().map(|c| c.point).collect();
+  assert_eq!(vec![(2, 0), (5, 0)], points);
+  assert_eq!(2, found.iter().map(|c| c.distance()).min().unwrap());
+  // (5, 0): 5 steps along the first wire, 2 + 7 + 2 + 2 along the second one
+  assert_eq!(18, found[1].combined_steps());
+}
+
+#[test]
+fn test_common_crossings() {
+  let all = wires(&["R8,U5,L5,D3", "U7,R6,D4,L4", "U3,R10"]);
+  assert_eq!(6, crossings(&all).len());
+  let common = common_crossings(&all);
+  assert_eq!(1, common.len());
+  assert_eq!((3, 3), common[0].point);
+  assert_eq!(20 + 20 + 6, common[0].combined_steps());
+}
+
+#[test]
+fn test_svg() {
+  let all = wires(&["R8,U5,L5,D3", "U7,R6,D4,L4"]);
+  let svg = svg(&all, &crossings(&all));
+  assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -8 10 9\">\n"));
+  assert!(svg.contains("<polyline points=\"0,0 8,0 8,-5 3,-5 3,-2\""));
+  assert_eq!(3, svg.matches("<circle").count());
+}
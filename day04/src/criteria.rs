@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// A password criterion, checked one digit at a time from left to right.
+///
+/// Rules are small automata: they only remember a `u32` state between digits. This is what
+/// allows `Criteria::count` to count matches without looking at every number.
+pub trait Rule {
+    /// The state before the first digit.
+    fn start(&self) -> u32;
+    /// The state after `digit`, or `None` if no number continuing like this can match.
+    fn step(&self, state: u32, digit: u8) -> Option<u32>;
+    /// Whether a number ending in `state` matches.
+    fn accept(&self, state: u32) -> bool;
+}
+
+/// The number has exactly this many digits.
+pub struct Length(pub usize);
+
+impl Rule for Length {
+    fn start(&self) -> u32 {
+        0
+    }
+
+    fn step(&self, state: u32, _digit: u8) -> Option<u32> {
+        if (state as usize) < self.0 {
+            Some(state + 1)
+        } else {
+            None
+        }
+    }
+
+    fn accept(&self, state: u32) -> bool {
+        state as usize == self.0
+    }
+}
+
+/// Going from left to right, the digits never decrease.
+pub struct NonDecreasing;
+
+impl Rule for NonDecreasing {
+    // state: the last digit
+    fn start(&self) -> u32 {
+        0
+    }
+
+    fn step(&self, state: u32, digit: u8) -> Option<u32> {
+        if digit as u32 >= state {
+            Some(digit as u32)
+        } else {
+            None
+        }
+    }
+
+    fn accept(&self, _state: u32) -> bool {
+        true
+    }
+}
+
+/// Two adjacent digits are the same (like 22 in 122345).
+pub struct AdjacentPair;
+
+impl Rule for AdjacentPair {
+    // state: the last digit + 1 (0 before the first digit), plus 16 once a pair was found
+    fn start(&self) -> u32 {
+        0
+    }
+
+    fn step(&self, state: u32, digit: u8) -> Option<u32> {
+        let last = state & 15;
+        let found = state & 16 != 0 || last == digit as u32 + 1;
+        Some(digit as u32 + 1 + if found { 16 } else { 0 })
+    }
+
+    fn accept(&self, state: u32) -> bool {
+        state & 16 != 0
+    }
+}
+
+/// Two adjacent digits are the same, and not part of a larger group of matching digits
+/// (like 33 in 111233, but not 444 in 123444).
+pub struct ExactPair;
+
+impl ExactPair {
+    fn unpack(state: u32) -> (u32, u32, bool) {
+        (state & 15, (state >> 4) & 3, state & 64 != 0)
+    }
+
+    fn pack(last: u32, run: u32, found: bool) -> u32 {
+        last | run << 4 | if found { 64 } else { 0 }
+    }
+}
+
+impl Rule for ExactPair {
+    // state: the last digit + 1, how often it was repeated (up to 3), and whether an exact
+    // pair was found before
+    fn start(&self) -> u32 {
+        0
+    }
+
+    fn step(&self, state: u32, digit: u8) -> Option<u32> {
+        let (last, run, found) = Self::unpack(state);
+        let digit = digit as u32 + 1;
+        if digit == last {
+            Some(Self::pack(last, (run + 1).min(3), found))
+        } else {
+            Some(Self::pack(digit, 1, found || run == 2))
+        }
+    }
+
+    fn accept(&self, state: u32) -> bool {
+        let (_, run, found) = Self::unpack(state);
+        found || run == 2
+    }
+}
+
+/// A check on all digits of a number at once.
+type Predicate = Box<dyn Fn(&[u8]) -> bool>;
+
+/// A set of rules every password has to satisfy.
+#[derive(Default)]
+pub struct Criteria {
+    rules: Vec<Box<dyn Rule>>,
+    predicates: Vec<Predicate>,
+}
+
+impl Criteria {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule<R: Rule + 'static>(mut self, rule: R) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Adds a check on all digits of a number at once. Such checks cannot be split into
+    /// steps, so `count` has to enumerate every number once there is one.
+    pub fn predicate<F: Fn(&[u8]) -> bool + 'static>(mut self, predicate: F) -> Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    fn start(&self) -> Vec<u32> {
+        self.rules.iter().map(|rule| rule.start()).collect()
+    }
+
+    fn step(&self, states: &[u32], digit: u8) -> Option<Vec<u32>> {
+        self.rules
+            .iter()
+            .zip(states.iter())
+            .map(|(rule, &state)| rule.step(state, digit))
+            .collect()
+    }
+
+    fn accept(&self, states: &[u32]) -> bool {
+        self.rules
+            .iter()
+            .zip(states.iter())
+            .all(|(rule, &state)| rule.accept(state))
+    }
+
+    pub fn matches(&self, n: u64) -> bool {
+        let digits = digits(n);
+        let mut states = self.start();
+        for &digit in &digits {
+            states = match self.step(&states, digit) {
+                Some(next) => next,
+                None => return false,
+            };
+        }
+        self.accept(&states) && self.predicates.iter().all(|predicate| predicate(&digits))
+    }
+
+    /// All matching numbers in `range`, checking them one by one.
+    pub fn enumerate(&self, range: RangeInclusive<u64>) -> impl Iterator<Item = u64> + '_ {
+        range.filter(move |&n| self.matches(n))
+    }
+
+    /// The number of matches in `range`. Unless there are predicates, this does not look at
+    /// every number, but counts digit by digit, so even ranges of 18-digit numbers are fine.
+    pub fn count(&self, range: RangeInclusive<u64>) -> u64 {
+        if !self.predicates.is_empty() {
+            return self.enumerate(range).count() as u64;
+        }
+        let (lower, upper) = range.into_inner();
+        if lower > upper {
+            return 0;
+        }
+        let below = match lower.checked_sub(1) {
+            Some(n) => self.count_up_to(n),
+            None => 0,
+        };
+        self.count_up_to(upper) - below
+    }
+
+    /// The number of matches in `0..=n`.
+    fn count_up_to(&self, n: u64) -> u64 {
+        let mut counter = Counter {
+            criteria: self,
+            limit: digits(n),
+            memo: HashMap::new(),
+        };
+        let zero = if self.matches(0) { 1 } else { 0 };
+        // numbers shorter than the limit are written with leading zeros, which no rule sees
+        zero + counter.count(0, true, None)
+    }
+}
+
+/// Digit DP for counting the matches in `0..=limit`, except 0.
+struct Counter<'a> {
+    criteria: &'a Criteria,
+    limit: Vec<u8>,
+    /// (position, rule states or `None` while there were only leading zeros) -> matches
+    memo: HashMap<(usize, Option<Vec<u32>>), u64>,
+}
+
+impl<'a> Counter<'a> {
+    /// Matches among the numbers which continue from `states` at digit `pos`; while `tight`,
+    /// the digits so far equal those of the limit, so the next one may not exceed it.
+    fn count(&mut self, pos: usize, tight: bool, states: Option<Vec<u32>>) -> u64 {
+        if pos == self.limit.len() {
+            let accepted = match &states {
+                Some(states) => self.criteria.accept(states),
+                None => false,
+            };
+            return if accepted { 1 } else { 0 };
+        }
+        if !tight {
+            if let Some(&result) = self.memo.get(&(pos, states.clone())) {
+                return result;
+            }
+        }
+
+        let max = if tight { self.limit[pos] } else { 9 };
+        let mut result = 0;
+        for digit in 0..=max {
+            let next = if states.is_none() && digit == 0 {
+                // still in the leading zeros
+                None
+            } else {
+                let current = states.clone().unwrap_or_else(|| self.criteria.start());
+                match self.criteria.step(&current, digit) {
+                    Some(next) => Some(next),
+                    None => continue,
+                }
+            };
+            result += self.count(pos + 1, tight && digit == max, next);
+        }
+
+        if !tight {
+            self.memo.insert((pos, states), result);
+        }
+        result
+    }
+}
+
+fn digits(n: u64) -> Vec<u8> {
+    n.to_string().bytes().map(|b| b - b'0').collect()
+}
+
+#[cfg(test)]
+fn part1_criteria() -> Criteria {
+    Criteria::new()
+        .rule(Length(6))
+        .rule(NonDecreasing)
+        .rule(AdjacentPair)
+}
+
+#[test]
+fn test_matches() {
+    let criteria = part1_criteria();
+    assert!(criteria.matches(111111));
+    assert!(!criteria.matches(223450));
+    assert!(!criteria.matches(123789));
+
+    let strict = Criteria::new()
+        .rule(Length(6))
+        .rule(NonDecreasing)
+        .rule(ExactPair);
+    assert!(strict.matches(112233));
+    assert!(!strict.matches(123444));
+    assert!(strict.matches(111122));
+}
+
+#[test]
+fn test_count_agrees_with_enumerate() {
+    let all = vec![
+        part1_criteria(),
+        Criteria::new().rule(ExactPair),
+        Criteria::new().rule(NonDecreasing).rule(ExactPair),
+        Criteria::new().rule(Length(3)),
+    ];
+    for criteria in &all {
+        for &(lower, upper) in &[(0, 0), (0, 1000), (7, 99), (1234, 56789), (100000, 200000)] {
+            assert_eq!(
+                criteria.enumerate(lower..=upper).count() as u64,
+                criteria.count(lower..=upper),
+                "{}..={}",
+                lower,
+                upper
+            );
+        }
+    }
+}
+
+#[test]
+fn test_count_predicate() {
+    let even = part1_criteria().predicate(|digits| digits[5] % 2 == 0);
+    assert_eq!(
+        part1_criteria()
+            .enumerate(100000..=299999)
+            .filter(|n| n % 2 == 0)
+            .count() as u64,
+        even.count(100000..=299999)
+    );
+}
+
+#[test]
+fn test_count_huge_range() {
+    // choosing 18 digits out of 1..=9 with repetition, in non-decreasing order
+    let criteria = Criteria::new().rule(Length(18)).rule(NonDecreasing);
+    assert_eq!(1562275, criteria.count(0..=u64::MAX));
+    // with 18 digits out of 9, some digit is always repeated, and the repeats are adjacent
+    let pairs = criteria.rule(AdjacentPair);
+    assert_eq!(1562275, pairs.count(0..=u64::MAX));
+}
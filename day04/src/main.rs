@@ -1,95 +1,87 @@
-fn is_six_digits(n: usize) -> bool {
-    return n >= 100000 && n <= 999999;
+mod criteria;
+
+use std::env;
+
+use criteria::{AdjacentPair, Criteria, ExactPair, Length, NonDecreasing};
+
+/*
+It is a six-digit number.
+The value is within the range given in your puzzle input.
+Two adjacent digits are the same (like 22 in 122345).
+Going from left to right, the digits never decrease; they only ever increase or stay the same (like 111123 or 135679).
+ */
+fn part1_criteria() -> Criteria {
+    Criteria::new()
+        .rule(Length(6))
+        .rule(NonDecreasing)
+        .rule(AdjacentPair)
 }
 
-#[test]
-fn test_is_six_digits() {
-    assert_eq!(is_six_digits(111111), true);
-    assert_eq!(is_six_digits(223450), true);
-    assert_eq!(is_six_digits(123789), true);
-    assert_eq!(is_six_digits(99999), false);
-    assert_eq!(is_six_digits(999999 + 1), false);
+/// Like part 1, but the two adjacent matching digits are not part of a larger group.
+fn part2_criteria() -> Criteria {
+    Criteria::new()
+        .rule(Length(6))
+        .rule(NonDecreasing)
+        .rule(ExactPair)
 }
 
-fn has_two_adjacent_digits(digits: &[usize]) -> bool {
-    let n = digits.len();
-    for i in 0..n - 1 {
-        if digits[i] == digits[i + 1] {
-            return true;
-        }
-    }
-    return false;
+#[test]
+fn test_is_six_digits() {
+    let criteria = Criteria::new().rule(Length(6));
+    assert!(criteria.matches(111111));
+    assert!(criteria.matches(223450));
+    assert!(criteria.matches(123789));
+    assert!(!criteria.matches(99999));
+    assert!(!criteria.matches(999999 + 1));
 }
 
 #[test]
 fn test_has_two_adjacent_digits() {
-    assert_eq!(has_two_adjacent_digits(&[1, 1, 1, 1, 1, 1]), true);
-    assert_eq!(has_two_adjacent_digits(&[2, 2, 3, 4, 5, 0]), true);
-    assert_eq!(has_two_adjacent_digits(&[1, 2, 3, 7, 8, 9]), false);
+    let criteria = Criteria::new().rule(AdjacentPair);
+    assert!(criteria.matches(111111));
+    assert!(criteria.matches(223450));
+    assert!(!criteria.matches(123789));
 }
 
-fn has_two_adjacent_digits_strict(digits: &[usize]) -> bool {
-    let n = digits.len();
-    for i in 0..n - 1 {
-        if digits[i] == digits[i + 1] {
-            if i >= 1 {
-                if digits[i - 1] == digits[i] {
-                    continue;
-                }
-            }
-            if i + 2 < n {
-                if digits[i + 1] == digits[i + 2] {
-                    continue;
-                }
-            }
-            return true;
-        }
-    }
-    return false;
+#[test]
+fn test_has_two_adjacent_digits_strict() {
+    let criteria = Criteria::new().rule(ExactPair);
+    assert!(criteria.matches(112233));
+    assert!(!criteria.matches(123444));
+    assert!(criteria.matches(111122));
 }
 
 #[test]
-fn test_has_two_adjacent_digits_strict() {
-    assert_eq!(has_two_adjacent_digits_strict(&[1, 1, 2, 2, 3, 3]), true);
-    assert_eq!(has_two_adjacent_digits_strict(&[1, 2, 3, 4, 4, 4]), false);
-    assert_eq!(has_two_adjacent_digits_strict(&[1, 1, 1, 1, 2, 2]), true);
+fn test_parts() {
+    assert_eq!(1790, part1_criteria().count(147981..=691423));
+    assert_eq!(1206, part2_criteria().count(147981..=691423));
 }
 
 fn main() {
-    let lower = 147981;
-    let upper = 691423;
-
-    let mut count1 = 0;
-    let mut count2 = 0;
-    for i in 0..10 {
-        for j in i..10 {
-            for k in j..10 {
-                for l in k..10 {
-                    for m in l..10 {
-                        for n in m..10 {
-                            let candidate = n + 10 * m + 100 * l + 1000 * k + 10000 * j + 100000 * i;
-
-                            /*
-                            It is a six-digit number.
-                            The value is within the range given in your puzzle input.
-                            Two adjacent digits are the same (like 22 in 122345).
-                            Going from left to right, the digits never decrease; they only ever increase or stay the same (like 111123 or 135679).
-                             */
-                            if candidate >= lower && candidate <= upper && is_six_digits(candidate) {
-                                let digits = [i, j, k, l, m, n];
-                                if has_two_adjacent_digits(&digits) {
-                                    count1 += 1;
-                                }
-                                if has_two_adjacent_digits_strict(&digits) {
-                                    count2 += 1;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    // day04 [<lower> <upper> [<digit sum>]]: count in another range, and optionally only the
+    // passwords whose digits add up to <digit sum>
+    let args: Vec<u64> = env::args()
+        .skip(1)
+        .map(|arg| arg.parse().expect("invalid number"))
+        .collect();
+    let (lower, upper) = match args[..] {
+        [] => (147981, 691423),
+        [lower, upper] | [lower, upper, _] => (lower, upper),
+        _ => panic!("usage: day04 [<lower> <upper> [<digit sum>]]"),
+    };
+    let digit_sum = |criteria: Criteria| match args.get(2) {
+        Some(&sum) => {
+            criteria.predicate(move |digits| digits.iter().map(|&d| d as u64).sum::<u64>() == sum)
         }
-    }
-    println!("Solution Part 1: {}", count1);
-    println!("Solution Part 2: {}", count2);
+        None => criteria,
+    };
+
+    println!(
+        "Solution Part 1: {}",
+        digit_sum(part1_criteria()).count(lower..=upper)
+    );
+    println!(
+        "Solution Part 2: {}",
+        digit_sum(part2_criteria()).count(lower..=upper)
+    );
 }
@@ -1,3 +1,4 @@
+mod orbit_map;
 mod solution;
 
 use env_logger;
@@ -5,7 +6,7 @@ use log::info;
 use std::env;
 use std::time::Instant;
 
-use solution::{part1, part2};
+use solution::{dot, part1, part2};
 
 fn main() {
     env_logger::init();
@@ -25,6 +26,9 @@ fn main() {
             solve_two = false;
         } else if part == "2" {
             solve_one = false;
+        } else if part == "dot" {
+            print!("{}", dot(&fname));
+            return;
         } else {
             panic!("invalid choice");
         }
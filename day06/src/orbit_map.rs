@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+
+use aoc2019::graph;
+
+/// The universal Center of Mass, which everything orbits directly or indirectly.
+pub const COM: &str = "COM";
+
+/// All objects and what they orbit, as a tree of parent pointers rooted at COM.
+#[derive(Debug)]
+pub struct OrbitMap {
+    /// object -> the object it orbits directly
+    parents: HashMap<String, String>,
+}
+
+impl OrbitMap {
+    /// Parses lines of the form `A)B` (B orbits A). Fails if a line is malformed, an object
+    /// orbits more than one center, the orbits form a cycle, or some object does not orbit COM.
+    pub fn parse(s: &str) -> Result<OrbitMap, String> {
+        let mut parents = HashMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let parts: Vec<&str> = line.trim().split(')').collect();
+            if parts.len() != 2 || parts.iter().any(|part| part.is_empty()) {
+                return Err(format!("Invalid orbit on line {}: {:?}", i + 1, line));
+            }
+            let (center, object) = (parts[0].to_string(), parts[1].to_string());
+            if let Some(other) = parents.get(&object) {
+                return Err(format!(
+                    "{} orbits both {} and {} (line {})",
+                    object,
+                    other,
+                    center,
+                    i + 1
+                ));
+            }
+            parents.insert(object, center);
+        }
+
+        let map = OrbitMap { parents };
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// Checks that following the parents from every object ends at COM.
+    fn validate(&self) -> Result<(), String> {
+        if let Some(center) = self.parents.get(COM) {
+            return Err(format!(
+                "{} must not orbit anything, but orbits {}",
+                COM, center
+            ));
+        }
+        if !self.parents.is_empty() && !self.parents.values().any(|center| center == COM) {
+            return Err(format!("Nothing orbits {}", COM));
+        }
+        let mut reaches_com: HashSet<&str> = HashSet::new();
+        reaches_com.insert(COM);
+        // sorted, so the same error is reported every time
+        let mut objects: Vec<&String> = self.parents.keys().collect();
+        objects.sort();
+        for object in objects {
+            let mut chain = vec![object.as_str()];
+            let mut current = object.as_str();
+            while !reaches_com.contains(current) {
+                current = match self.parents.get(current) {
+                    Some(center) => center,
+                    None => {
+                        return Err(format!(
+                            "{} does not orbit {}: {}",
+                            object,
+                            COM,
+                            chain.join(" -> ")
+                        ))
+                    }
+                };
+                if chain.contains(&current) {
+                    chain.push(current);
+                    return Err(format!("Cycle in orbits: {}", chain.join(" -> ")));
+                }
+                chain.push(current);
+            }
+            reaches_com.extend(chain);
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, object: &str) -> bool {
+        object == COM || self.parents.contains_key(object)
+    }
+
+    /// The object `object` orbits directly.
+    pub fn center(&self, object: &str) -> Option<&str> {
+        self.parents.get(object).map(|center| center.as_str())
+    }
+
+    /// The number of direct orbits; every object except COM orbits exactly one other object.
+    pub fn direct_orbits(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// The number of direct and indirect orbits, i.e. the sum of all depths.
+    pub fn total_orbits(&self) -> usize {
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (object, center) in &self.parents {
+            children.entry(center).or_default().push(object);
+        }
+        let distances = graph::bfs(COM, |object| {
+            children.get(object).cloned().unwrap_or_default()
+        });
+        distances.values().sum()
+    }
+
+    pub fn indirect_orbits(&self) -> usize {
+        self.total_orbits() - self.direct_orbits()
+    }
+
+    /// How many objects `object` orbits directly or indirectly; 0 for COM.
+    pub fn depth(&self, object: &str) -> Option<usize> {
+        if !self.contains(object) {
+            return None;
+        }
+        Some(self.ancestors(object).len())
+    }
+
+    /// The objects `object` orbits, from the direct center up to COM.
+    fn ancestors(&self, object: &str) -> Vec<String> {
+        graph::ancestors(&self.parents, object.to_string())
+    }
+
+    /// The deepest object which both `a` and `b` orbit (or are).
+    pub fn lowest_common_ancestor(&self, a: &str, b: &str) -> Option<String> {
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+        graph::lowest_common_ancestor(&self.parents, a.to_string(), b.to_string())
+    }
+
+    /// The objects passed on the way from `a` to `b`, both included.
+    fn path(&self, a: &str, b: &str) -> Option<Vec<String>> {
+        let common = self.lowest_common_ancestor(a, b)?;
+        let up = |start: &str| {
+            let mut result = vec![start.to_string()];
+            result.extend(self.ancestors(start));
+            let end = result.iter().position(|object| *object == common).unwrap();
+            result.truncate(end + 1);
+            result
+        };
+        let mut path = up(a);
+        let mut down = up(b);
+        down.pop(); // the common ancestor is already on the path
+        path.extend(down.into_iter().rev());
+        Some(path)
+    }
+
+    /// The objects visited when moving from the object `from` orbits to the object `to` orbits,
+    /// both included.
+    pub fn transfer_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        self.path(self.center(from)?, self.center(to)?)
+    }
+
+    /// The number of orbital transfers needed to orbit the same object as `to`.
+    pub fn transfers(&self, from: &str, to: &str) -> Option<usize> {
+        Some(self.transfer_path(from, to)?.len() - 1)
+    }
+
+    /// The orbits as a Graphviz graph, with an edge from every center to each object orbiting it.
+    pub fn to_dot(&self) -> String {
+        let mut orbits: Vec<(&String, &String)> = self
+            .parents
+            .iter()
+            .map(|(object, center)| (center, object))
+            .collect();
+        orbits.sort();
+        let mut result = String::from("digraph orbits {\n");
+        for (center, object) in orbits {
+            result.push_str(&format!("    \"{}\" -> \"{}\";\n", center, object));
+        }
+        result.push_str("}\n");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "COM)B
+B)C
+C)D
+D)E
+E)F
+B)G
+G)H
+D)I
+E)J
+J)K
+K)L
+K)YOU
+I)SAN";
+
+    #[test]
+    fn orbits_test() {
+        let map = OrbitMap::parse(EXAMPLE).unwrap();
+        assert_eq!(13, map.direct_orbits());
+        assert_eq!(54, map.total_orbits());
+        assert_eq!(41, map.indirect_orbits());
+        assert_eq!(Some(0), map.depth(COM));
+        assert_eq!(Some(3), map.depth("D"));
+        assert_eq!(Some(7), map.depth("L"));
+        assert_eq!(None, map.depth("X"));
+    }
+
+    #[test]
+    fn transfers_test() {
+        let map = OrbitMap::parse(EXAMPLE).unwrap();
+        assert_eq!(
+            Some("D".to_string()),
+            map.lowest_common_ancestor("YOU", "SAN")
+        );
+        assert_eq!(Some("E".to_string()), map.lowest_common_ancestor("E", "L"));
+        assert_eq!(
+            vec!["K", "J", "E", "D", "I"],
+            map.transfer_path("YOU", "SAN").unwrap()
+        );
+        assert_eq!(Some(4), map.transfers("YOU", "SAN"));
+        assert_eq!(Some(0), map.transfers("YOU", "L"));
+        assert_eq!(None, map.transfers("YOU", "X"));
+    }
+
+    #[test]
+    fn validation_test() {
+        let error = |s: &str| OrbitMap::parse(s).unwrap_err();
+        assert_eq!("Invalid orbit on line 2: \"BC\"", error("COM)B\nBC"));
+        assert_eq!(
+            "C orbits both A and B (line 4)",
+            error("COM)A\nCOM)B\nA)C\nB)C")
+        );
+        assert_eq!("Nothing orbits COM", error("A)B"));
+        assert_eq!(
+            "COM must not orbit anything, but orbits A",
+            error("COM)A\nA)COM")
+        );
+        assert_eq!("C does not orbit COM: C -> B", error("COM)A\nB)C"));
+        assert_eq!("Cycle in orbits: B -> C -> B", error("COM)A\nB)C\nC)B"));
+    }
+
+    #[test]
+    fn to_dot_test() {
+        let map = OrbitMap::parse("COM)B\nB)C\nCOM)A").unwrap();
+        assert_eq!(
+            "digraph orbits {\n    \"B\" -> \"C\";\n    \"COM\" -> \"A\";\n    \"COM\" -> \"B\";\n}\n",
+            map.to_dot()
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use std::fmt;
+
+use aoc2019::grid::{Grid, Point};
+use aoc2019::ocr;
+
+/// A pixel as stored in a layer: `0` is black, `1` is white and `2` is transparent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+  Black,
+  White,
+  Transparent,
+}
+
+impl Color {
+  pub fn from_digit(digit: u8) -> Option<Color> {
+    match digit {
+      0 => Some(Color::Black),
+      1 => Some(Color::White),
+      2 => Some(Color::Transparent),
+      _ => None,
+    }
+  }
+
+  pub fn digit(self) -> u8 {
+    match self {
+      Color::Black => 0,
+      Color::White => 1,
+      Color::Transparent => 2,
+    }
+  }
+}
+
+/// One layer of an image, row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+  width: usize,
+  pixels: Vec<u8>,
+}
+
+impl Layer {
+  #[cfg(test)]
+  pub fn rows(&self) -> Vec<Vec<u8>> {
+    self.pixels.chunks(self.width).map(|row| row.to_vec()).collect()
+  }
+
+  pub fn count_occurences(&self, digit: u8) -> usize {
+    self.pixels.iter().filter(|&&d| d == digit).count()
+  }
+}
+
+/// An image in the Space Image Format: layers of digits, the first layer in front.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+  width: usize,
+  height: usize,
+  layers: Vec<Layer>,
+}
+
+impl Image {
+  /// Parses the digits of a `width` x `height` image. Trailing whitespace is ignored, and every
+  /// digit has to be a `Color`.
+  pub fn parse(data: &str, width: usize, height: usize) -> Result<Image, String> {
+    if width == 0 || height == 0 {
+      return Err(format!("Invalid image size {}x{}", width, height));
+    }
+    let digits = data
+      .trim_end()
+      .chars()
+      .enumerate()
+      .map(|(i, c)| match c.to_digit(10).and_then(|d| Color::from_digit(d as u8)) {
+        Some(color) => Ok(color.digit()),
+        None => Err(format!("Invalid pixel {:?} at position {}", c, i)),
+      })
+      .collect::<Result<Vec<u8>, String>>()?;
+    let size = width * height;
+    if digits.is_empty() || digits.len() % size != 0 {
+      return Err(format!(
+        "Image data of length {} does not consist of {}x{} layers",
+        digits.len(),
+        width,
+        height
+      ));
+    }
+    let layers = digits
+      .chunks(size)
+      .map(|pixels| Layer {
+        width,
+        pixels: pixels.to_vec(),
+      })
+      .collect();
+    Ok(Image {
+      width,
+      height,
+      layers,
+    })
+  }
+
+  /// Stacks the pixels of `bitmap` onto `layers` layers. Every pixel ends up on some layer, with
+  /// transparent pixels above it and the opposite color below it, so decoding only works if
+  /// transparency is handled correctly.
+  pub fn encode(bitmap: &Bitmap, layers: usize) -> Image {
+    assert!(layers > 0, "An image needs at least one layer");
+    let mut result: Vec<Layer> = (0..layers)
+      .map(|_| Layer {
+        width: bitmap.width,
+        pixels: vec![Color::Transparent.digit(); bitmap.pixels.len()],
+      })
+      .collect();
+    for (i, &color) in bitmap.pixels.iter().enumerate() {
+      let below = match color {
+        Color::Black => Color::White,
+        Color::White => Color::Black,
+        Color::Transparent => continue,
+      };
+      let depth = i % layers;
+      result[depth].pixels[i] = color.digit();
+      for layer in &mut result[depth + 1..] {
+        layer.pixels[i] = below.digit();
+      }
+    }
+    Image {
+      width: bitmap.width,
+      height: bitmap.height,
+      layers: result,
+    }
+  }
+
+  pub fn layers(&self) -> &[Layer] {
+    &self.layers
+  }
+
+  /// The number of 1 digits multiplied by the number of 2 digits, on the layer with the fewest
+  /// 0 digits.
+  pub fn checksum(&self) -> usize {
+    let layer = self
+      .layers
+      .iter()
+      .min_by_key(|layer| layer.count_occurences(0))
+      .unwrap();
+    layer.count_occurences(1) * layer.count_occurences(2)
+  }
+
+  /// The visible image: every pixel takes the color of the first layer where it is not
+  /// transparent. Pixels which are transparent on all layers stay transparent.
+  pub fn composite(&self) -> Bitmap {
+    let mut pixels = vec![Color::Transparent; self.width * self.height];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+      for layer in &self.layers {
+        // `parse` and `encode` only store colors
+        let color = Color::from_digit(layer.pixels[i]).unwrap();
+        if color != Color::Transparent {
+          *pixel = color;
+          break;
+        }
+      }
+    }
+    Bitmap {
+      width: self.width,
+      height: self.height,
+      pixels,
+    }
+  }
+}
+
+impl fmt::Display for Image {
+  /// The image in the Space Image Format, i.e. all digits in a row.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for layer in &self.layers {
+      for &digit in &layer.pixels {
+        write!(f, "{}", digit)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// A single layer of colors, e.g. a decoded image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+  width: usize,
+  height: usize,
+  pixels: Vec<Color>,
+}
+
+impl Bitmap {
+  /// Reads rows of `#` (white), `.` (black) and ` ` (transparent). Fails on an empty bitmap.
+  pub fn parse(s: &str) -> Result<Bitmap, String> {
+    let rows: Vec<&str> = s.lines().collect();
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    if width == 0 {
+      return Err("The bitmap is empty".to_string());
+    }
+    let mut pixels = Vec::new();
+    for (y, row) in rows.iter().enumerate() {
+      if row.chars().count() != width {
+        return Err(format!("Row {} is not {} pixels wide", y + 1, width));
+      }
+      for c in row.chars() {
+        pixels.push(match c {
+          '#' => Color::White,
+          '.' => Color::Black,
+          ' ' => Color::Transparent,
+          _ => return Err(format!("Invalid pixel {:?} in row {}", c, y + 1)),
+        });
+      }
+    }
+    Ok(Bitmap {
+      width,
+      height: rows.len(),
+      pixels,
+    })
+  }
+
+  pub fn get(&self, x: usize, y: usize) -> Color {
+    self.pixels[y * self.width + x]
+  }
+
+  /// The bitmap as text, with `white` for white pixels and spaces for everything else.
+  pub fn render(&self, white: char) -> String {
+    let mut result = String::new();
+    for row in self.pixels.chunks(self.width) {
+      for &color in row {
+        result.push(if color == Color::White { white } else { ' ' });
+      }
+      result.push('\n');
+    }
+    result
+  }
+
+  /// The bitmap as a plain PPM (P3) image. Transparent pixels are drawn black.
+  pub fn to_ppm(&self) -> String {
+    let mut result = format!("P3\n{} {}\n255\n", self.width, self.height);
+    for row in self.pixels.chunks(self.width) {
+      let values: Vec<&str> = row
+        .iter()
+        .map(|&color| if color == Color::White { "255 255 255" } else { "0 0 0" })
+        .collect();
+      result.push_str(&values.join(" "));
+      result.push('\n');
+    }
+    result
+  }
+
+  /// The bitmap as an SVG image with one square per pixel. Transparent pixels are left out.
+  pub fn to_svg(&self, scale: usize) -> String {
+    let mut result = format!(
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+      self.width * scale,
+      self.height * scale
+    );
+    for y in 0..self.height {
+      for x in 0..self.width {
+        let fill = match self.get(x, y) {
+          Color::Black => "black",
+          Color::White => "white",
+          Color::Transparent => continue,
+        };
+        result.push_str(&format!(
+          "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+          x * scale,
+          y * scale,
+          scale,
+          scale,
+          fill
+        ));
+      }
+    }
+    result.push_str("</svg>\n");
+    result
+  }
+
+  /// Reads the letters drawn in white.
+  pub fn ocr(&self) -> Result<String, String> {
+    ocr::read(&self.white())
+  }
+
+  fn white(&self) -> Grid<bool> {
+    let mut grid = Grid::new(self.width, self.height, false);
+    for (i, &color) in self.pixels.iter().enumerate() {
+      let p = Point::new((i % self.width) as i64, (i / self.width) as i64);
+      grid[p] = color == Color::White;
+    }
+    grid
+  }
+}
+
+#[test]
+fn test_parse_image() {
+  let image = Image::parse("012210201102", 3, 2).unwrap();
+  assert_eq!(image.layers().len(), 2);
+  let rows: Vec<Vec<Vec<u8>>> = image.layers().iter().map(|layer| layer.rows()).collect();
+  assert_eq!(rows, vec![vec![vec![0, 1, 2], vec![2, 1, 0]], vec![vec![2, 0, 1], vec![1, 0, 2]]]);
+}
+
+#[test]
+fn test_parse_errors() {
+  assert_eq!(
+    Err("Invalid pixel 'x' at position 1".to_string()),
+    Image::parse("0x12\n", 2, 1)
+  );
+  assert_eq!(
+    Err("Invalid pixel '3' at position 1".to_string()),
+    Image::parse("0312\n", 2, 1)
+  );
+  assert_eq!(
+    Err("Image data of length 5 does not consist of 2x2 layers".to_string()),
+    Image::parse("01201", 2, 2)
+  );
+  assert_eq!(Err("Invalid image size 0x2".to_string()), Image::parse("01", 0, 2));
+  assert_eq!(Err("The bitmap is empty".to_string()), Bitmap::parse(""));
+  assert_eq!(Err("The bitmap is empty".to_string()), Bitmap::parse("\n"));
+}
+
+#[test]
+fn test_statistics() {
+  let image = Image::parse("001122011122", 3, 2).unwrap();
+  assert_eq!(2, image.layers()[0].count_occurences(0));
+  assert_eq!(1, image.layers()[1].count_occurences(0));
+  assert_eq!(3 * 2, image.checksum());
+}
+
+#[test]
+fn test_composite() {
+  let image = Image::parse("0222112222120000", 2, 2).unwrap();
+  assert_eq!(Bitmap::parse(".#\n#.").unwrap(), image.composite());
+  // pixels which are transparent everywhere stay transparent
+  let image = Image::parse("22122122", 2, 2).unwrap();
+  assert_eq!(Bitmap::parse(" #\n# ").unwrap(), image.composite());
+  assert_eq!(" #\n# \n", image.composite().render('#'));
+}
+
+#[test]
+fn test_encode() {
+  let bitmap = Bitmap::parse("#..#\n.# #\n#.#.").unwrap();
+  for layers in 1..5 {
+    let image = Image::encode(&bitmap, layers);
+    assert_eq!(layers, image.layers().len());
+    assert_eq!(bitmap, image.composite());
+    let decoded = Image::parse(&image.to_string(), 4, 3).unwrap();
+    assert_eq!(image, decoded);
+  }
+}
+
+#[test]
+fn test_export() {
+  let bitmap = Bitmap::parse("#.\n #").unwrap();
+  assert_eq!(
+    "P3\n2 2\n255\n255 255 255 0 0 0\n0 0 0 255 255 255\n",
+    bitmap.to_ppm()
+  );
+  let svg = bitmap.to_svg(10);
+  assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"20\">\n"));
+  assert!(svg.contains("<rect x=\"10\" y=\"10\" width=\"10\" height=\"10\" fill=\"white\"/>"));
+  assert_eq!(3, svg.matches("<rect").count());
+}
+
+#[test]
+fn test_ocr() {
+  let bitmap = Bitmap::parse(
+    "\
+#..#.####.#....#.....##..
+#..#.#....#....#....#..#.
+####.###..#....#....#..#.
+#..#.#....#....#....#..#.
+#..#.#....#....#....#..#.
+#..#.####.####.####..##..",
+  )
+  .unwrap();
+  assert_eq!(Ok("HELLO".to_string()), bitmap.ocr());
+
+  let unknown = Bitmap::parse("#.#\n.#.\n#.#\n.#.\n#.#\n.#.").unwrap();
+  assert_eq!(
+    Err("Unrecognized glyph at column 0:\n#.#\n.#.\n#.#\n.#.\n#.#\n.#.".to_string()),
+    unknown.ocr()
+  );
+}
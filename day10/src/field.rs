@@ -0,0 +1,352 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use aoc2019::grid::{Grid, Point};
+
+/// The direction the laser starts in and which way it turns.
+#[derive(Debug, Clone, Copy)]
+pub struct Laser {
+    /// any vector pointing in the start direction; asteroids exactly in this direction are hit
+    /// first
+    pub start: Point,
+    pub clockwise: bool,
+}
+
+impl Default for Laser {
+    /// Starts pointing up and rotates clockwise.
+    fn default() -> Self {
+        Self {
+            start: Point::NORTH,
+            clockwise: true,
+        }
+    }
+}
+
+impl Laser {
+    /// Orders two directions by how far the laser has to turn to reach them. Only integer
+    /// cross and dot products are used, so directions which are equal compare as equal.
+    pub fn cmp_angle(&self, a: Point, b: Point) -> Ordering {
+        // y grows downwards, so a positive cross product means turning clockwise
+        let turn = |u: Point, v: Point| {
+            let cross = u.x * v.y - u.y * v.x;
+            if self.clockwise {
+                cross
+            } else {
+                -cross
+            }
+        };
+        // 0: within the first half turn (including the start direction), 1: the second half
+        let half = |v: Point| {
+            let t = turn(self.start, v);
+            let dot = self.start.x * v.x + self.start.y * v.y;
+            if t > 0 || (t == 0 && dot > 0) {
+                0
+            } else {
+                1
+            }
+        };
+        half(a)
+            .cmp(&half(b))
+            .then_with(|| turn(a, b).cmp(&0).reverse())
+    }
+}
+
+/// A map of asteroids, as seen from any potential monitoring station.
+pub struct AsteroidField {
+    asteroids: Vec<Point>,
+    width: usize,
+    height: usize,
+}
+
+impl AsteroidField {
+    pub fn parse(s: &str) -> Self {
+        let grid = Grid::parse(s.trim_end(), |_, c| c == '#');
+        Self {
+            asteroids: grid.positions(|&asteroid| asteroid),
+            width: grid.width(),
+            height: grid.height(),
+        }
+    }
+
+    pub fn asteroids(&self) -> &[Point] {
+        &self.asteroids
+    }
+
+    /// All other asteroids as seen from `station`, grouped by the direction they are in
+    /// (reduced to the shortest integer vector). Each group is ordered nearest first, so only
+    /// the first asteroid of a group is visible.
+    pub fn lines_of_sight(&self, station: Point) -> HashMap<Point, Vec<Point>> {
+        let mut lines: HashMap<Point, Vec<Point>> = HashMap::new();
+        for &other in &self.asteroids {
+            if other != station {
+                lines
+                    .entry(direction(other - station))
+                    .or_default()
+                    .push(other);
+            }
+        }
+        for line in lines.values_mut() {
+            line.sort_by_key(|&p| p.manhattan(station));
+        }
+        lines
+    }
+
+    /// The asteroids which can be seen from `station`.
+    pub fn visible(&self, station: Point) -> Vec<Point> {
+        self.lines_of_sight(station)
+            .values()
+            .map(|line| line[0])
+            .collect()
+    }
+
+    pub fn visible_count(&self, station: Point) -> usize {
+        self.lines_of_sight(station).len()
+    }
+
+    /// How many other asteroids can be seen from every asteroid.
+    pub fn visibility_counts(&self) -> HashMap<Point, usize> {
+        self.asteroids
+            .iter()
+            .map(|&p| (p, self.visible_count(p)))
+            .collect()
+    }
+
+    /// The asteroid which sees the most other asteroids, and how many it sees.
+    pub fn best_station(&self) -> Option<(Point, usize)> {
+        // ties go to the asteroid which comes first in reading order
+        self.asteroids
+            .iter()
+            .map(|&p| (p, self.visible_count(p)))
+            .fold(None, |best, (p, count)| match best {
+                Some((_, best_count)) if best_count >= count => best,
+                _ => Some((p, count)),
+            })
+    }
+
+    /// The order in which a laser at `station` vaporizes all other asteroids: it hits the
+    /// nearest asteroid in each direction per rotation.
+    pub fn firing_order(&self, station: Point, laser: Laser) -> Vec<Point> {
+        let mut lines: Vec<(Point, Vec<Point>)> =
+            self.lines_of_sight(station).into_iter().collect();
+        lines.sort_by(|a, b| laser.cmp_angle(a.0, b.0));
+
+        // the station does not have to be an asteroid itself
+        let mut result = Vec::with_capacity(self.asteroids.len());
+        let mut rotation = 0;
+        while lines.iter().any(|(_, line)| rotation < line.len()) {
+            for (_, line) in &lines {
+                if let Some(&p) = line.get(rotation) {
+                    result.push(p);
+                }
+            }
+            rotation += 1;
+        }
+        debug!("vaporized everything after {} rotations", rotation);
+        result
+    }
+
+    /// The `n`th asteroid to be vaporized, counting from 1.
+    pub fn nth_vaporized(&self, station: Point, laser: Laser, n: usize) -> Option<Point> {
+        self.firing_order(station, laser)
+            .get(n.checked_sub(1)?)
+            .copied()
+    }
+
+    /// The field with every asteroid replaced by a digit from 0 to 9: how many others it sees,
+    /// relative to the best station.
+    pub fn heatmap(&self) -> String {
+        let counts = self.visibility_counts();
+        let max = counts.values().copied().max().unwrap_or(0).max(1);
+        let mut grid = Grid::new(self.width, self.height, '.');
+        for (p, count) in counts {
+            grid[p] = std::char::from_digit((count * 9 / max) as u32, 10).unwrap();
+        }
+        grid.render(|&c| c)
+    }
+}
+
+/// `v` divided by the greatest common divisor of its coordinates.
+fn direction(v: Point) -> Point {
+    let d = gcd(v.x.abs(), v.y.abs());
+    Point::new(v.x / d, v.y / d)
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        let _ = env_logger::builder().is_test(true).try_init();
+    }
+
+    const SMALL: &str = ".#..#
+.....
+#####
+....#
+...##";
+
+    const LARGE: &str = ".#..##.###...#######
+##.############..##.
+.#.######.########.#
+.###.#######.####.#.
+#####.##.#.##.###.##
+..#####..#.#########
+####################
+#.####....###.#.#.##
+##.#################
+#####.##.###..####..
+..######..##.#######
+####.##.####...##..#
+.#####..#.######.###
+##...#.##########...
+#.##########.#######
+.####.#.###.###.#.##
+....##.##.###..#####
+.#.#.###########.###
+#.#.#.#####.####.###
+###.##.####.##.#..##";
+
+    fn points(v: &[(i64, i64)]) -> Vec<Point> {
+        v.iter().map(|&p| p.into()).collect()
+    }
+
+    #[test]
+    fn test_parse_asteroids() {
+        init();
+
+        let field = AsteroidField::parse(SMALL);
+        assert_eq!(
+            field.asteroids(),
+            &points(&[
+                (1, 0),
+                (4, 0),
+                (0, 2),
+                (1, 2),
+                (2, 2),
+                (3, 2),
+                (4, 2),
+                (4, 3),
+                (3, 4),
+                (4, 4)
+            ])[..]
+        );
+    }
+
+    #[test]
+    fn test_count_visible_points() {
+        init();
+
+        let field = AsteroidField::parse(SMALL);
+        let expected = [7, 7, 6, 7, 7, 7, 5, 7, 8, 7];
+        for (&p, &count) in field.asteroids().iter().zip(expected.iter()) {
+            assert_eq!(field.visible_count(p), count, "{}", p);
+            assert_eq!(field.visible(p).len(), count);
+        }
+        assert_eq!(Some((Point::new(3, 4), 8)), field.best_station());
+    }
+
+    #[test]
+    fn test_vaporize() {
+        init();
+
+        let field = AsteroidField::parse(LARGE);
+        assert_eq!(Some((Point::new(11, 13), 210)), field.best_station());
+
+        let station = Point::new(11, 13);
+        let vap_points = field.firing_order(station, Laser::default());
+        assert_eq!(field.asteroids().len() - 1, vap_points.len());
+        assert_eq!(vap_points[0], Point::new(11, 12));
+        assert_eq!(vap_points[1], Point::new(12, 1));
+        assert_eq!(vap_points[2], Point::new(12, 2));
+        assert_eq!(vap_points[9], Point::new(12, 8));
+        assert_eq!(vap_points[19], Point::new(16, 0));
+        assert_eq!(vap_points[49], Point::new(16, 9));
+        assert_eq!(vap_points[99], Point::new(10, 16));
+        assert_eq!(vap_points[198], Point::new(9, 6));
+        assert_eq!(vap_points[199], Point::new(8, 2));
+        assert_eq!(vap_points[200], Point::new(10, 9));
+        assert_eq!(vap_points[298], Point::new(11, 1));
+
+        assert_eq!(
+            Some(Point::new(8, 2)),
+            field.nth_vaporized(station, Laser::default(), 200)
+        );
+        assert_eq!(None, field.nth_vaporized(station, Laser::default(), 0));
+        assert_eq!(None, field.nth_vaporized(station, Laser::default(), 300));
+    }
+
+    #[test]
+    fn test_laser_direction() {
+        init();
+
+        let field = AsteroidField::parse("#.#\n...\n#.#");
+        let station = Point::new(0, 0);
+        let right = Laser {
+            start: Point::EAST,
+            clockwise: true,
+        };
+        assert_eq!(
+            points(&[(2, 0), (2, 2), (0, 2)]),
+            field.firing_order(station, right)
+        );
+        let down = Laser {
+            start: Point::SOUTH,
+            clockwise: false,
+        };
+        assert_eq!(
+            points(&[(0, 2), (2, 2), (2, 0)]),
+            field.firing_order(station, down)
+        );
+    }
+
+    #[test]
+    fn test_empty_station() {
+        init();
+
+        // two asteroids behind each other, so the last one needs a second rotation
+        let field = AsteroidField::parse("##.\n.#.\n..#\n#..");
+        let station = Point::new(1, 2);
+        let order = points(&[(1, 1), (2, 2), (0, 3), (0, 0), (1, 0)]);
+        assert_eq!(order, field.firing_order(station, Laser::default()));
+        assert_eq!(
+            Some(Point::new(1, 0)),
+            field.nth_vaporized(station, Laser::default(), 5)
+        );
+    }
+
+    #[test]
+    fn test_cmp_angle() {
+        let laser = Laser::default();
+        let compass = points(&[
+            (0, -1),
+            (1, -2),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-2, -1),
+        ]);
+        for (i, &a) in compass.iter().enumerate() {
+            for (j, &b) in compass.iter().enumerate() {
+                assert_eq!(i.cmp(&j), laser.cmp_angle(a, b), "{} vs {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_heatmap() {
+        init();
+
+        let field = AsteroidField::parse(SMALL);
+        assert_eq!(".7..7\n.....\n67775\n....7\n...97\n", field.heatmap());
+    }
+}
@@ -0,0 +1,291 @@
+use aoc2019::grid::{Point, SparseGrid};
+use aoc2019::intcode::IntcodeProgram;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Color {
+    Black = 0,
+    White = 1,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub fn turn(self, turn: Turn) -> Direction {
+        match (self, turn) {
+            (Direction::North, Turn::Left) => Direction::West,
+            (Direction::East, Turn::Left) => Direction::North,
+            (Direction::South, Turn::Left) => Direction::East,
+            (Direction::West, Turn::Left) => Direction::South,
+            (Direction::North, Turn::Right) => Direction::East,
+            (Direction::East, Turn::Right) => Direction::South,
+            (Direction::South, Turn::Right) => Direction::West,
+            (Direction::West, Turn::Right) => Direction::North,
+        }
+    }
+
+    pub fn delta(self) -> Point {
+        match self {
+            Direction::North => Point::NORTH,
+            Direction::South => Point::SOUTH,
+            Direction::East => Point::EAST,
+            Direction::West => Point::WEST,
+        }
+    }
+
+    /// The robot as drawn in frames.
+    fn arrow(self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::East => '>',
+            Direction::South => 'v',
+            Direction::West => '<',
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Panel {
+    pub color: Color,
+    /// how often the robot painted this panel
+    pub visits: usize,
+}
+
+/// What the robot did in one step: it painted the panel at `position` with `color`, then turned
+/// to face `direction` and moved one panel ahead.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Step {
+    pub position: Point,
+    pub color: Color,
+    pub direction: Direction,
+}
+
+/// The emergency hull painting robot, driven by an Intcode program. Every panel starts black,
+/// except the one the robot starts on.
+///
+/// The robot is an iterator over the steps it takes, so the painting can be watched as it
+/// happens; `run` just paints until the program halts.
+#[derive(Debug, Clone)]
+pub struct HullRobot {
+    program: IntcodeProgram,
+    position: Point,
+    direction: Direction,
+    hull: SparseGrid<Panel>,
+    halted: bool,
+}
+
+impl HullRobot {
+    pub fn new(program: IntcodeProgram, starting_color: Color) -> Self {
+        let mut hull = SparseGrid::new();
+        hull.set(
+            Point::default(),
+            Panel {
+                color: starting_color,
+                visits: 0,
+            },
+        );
+        Self {
+            program,
+            position: Point::default(),
+            direction: Direction::North,
+            hull,
+            halted: false,
+        }
+    }
+
+    /// Paints until the program halts. Returns every panel the robot has been on, painted or
+    /// not.
+    pub fn run(&mut self) -> &SparseGrid<Panel> {
+        self.by_ref().for_each(drop);
+        &self.hull
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn color(&self, p: Point) -> Color {
+        self.hull.get(p).map_or(Color::Black, |panel| panel.color)
+    }
+
+    pub fn visits(&self, p: Point) -> usize {
+        self.hull.get(p).map_or(0, |panel| panel.visits)
+    }
+
+    /// The number of panels painted at least once.
+    pub fn painted_count(&self) -> usize {
+        self.hull
+            .iter()
+            .filter(|(_, panel)| panel.visits > 0)
+            .count()
+    }
+
+    /// The white panels, cropped to the smallest box around them.
+    pub fn render(&self) -> String {
+        let white: SparseGrid<()> = self
+            .hull
+            .iter()
+            .filter(|(_, panel)| panel.color == Color::White)
+            .map(|(p, _)| (p, ()))
+            .collect();
+        match white.bounding_box() {
+            Some((min, max)) => self.draw(min, max, false),
+            None => String::new(),
+        }
+    }
+
+    /// Draws the panels between `min` and `max`, optionally with the robot on top.
+    fn draw(&self, min: Point, max: Point, with_robot: bool) -> String {
+        let mut result = String::new();
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let p = Point::new(x, y);
+                result.push(if with_robot && p == self.position {
+                    self.direction.arrow()
+                } else if self.color(p) == Color::White {
+                    '#'
+                } else {
+                    ' '
+                });
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Paints until the program halts and draws the hull after every step (the first frame
+    /// shows the robot before it starts). All frames cover the same area, so they line up.
+    pub fn frames(mut self) -> Vec<String> {
+        let mut area = SparseGrid::new();
+        area.set(self.position, ());
+        for step in self.clone() {
+            area.set(step.position, ());
+            area.set(step.position + step.direction.delta(), ());
+        }
+        let (min, max) = area.bounding_box().unwrap();
+
+        let mut frames = vec![self.draw(min, max, true)];
+        while self.next().is_some() {
+            frames.push(self.draw(min, max, true));
+        }
+        frames
+    }
+}
+
+impl Iterator for HullRobot {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        if self.halted {
+            return None;
+        }
+        let mut output = Vec::new();
+        let status = self
+            .program
+            .run(&[self.color(self.position) as i64], &mut output);
+        if !status.is_active() {
+            self.halted = true;
+        }
+        if output.len() < 2 {
+            return None;
+        }
+        let color = match output[0] {
+            0 => Color::Black,
+            1 => Color::White,
+            other => panic!("Unsupported color: {}", other),
+        };
+        let turn = match output[1] {
+            0 => Turn::Left,
+            1 => Turn::Right,
+            other => panic!("Unsupported turn: {}", other),
+        };
+
+        let position = self.position;
+        let panel = self.hull.entry(position, || Panel {
+            color: Color::Black,
+            visits: 0,
+        });
+        panel.color = color;
+        panel.visits += 1;
+        self.direction = self.direction.turn(turn);
+        self.position = position + self.direction.delta();
+        debug!("direction: {:?}, pos: {:?}", self.direction, self.position);
+        Some(Step {
+            position,
+            color,
+            direction: self.direction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A program which ignores the colors it sees and answers with `moves`, as in the example.
+    fn scripted(moves: &[(i64, i64)]) -> IntcodeProgram {
+        let mut code = Vec::new();
+        for &(color, turn) in moves {
+            code.extend_from_slice(&[3, 1000, 104, color, 104, turn]);
+        }
+        code.push(99);
+        IntcodeProgram::new(code)
+    }
+
+    const EXAMPLE: [(i64, i64); 7] = [(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)];
+
+    #[test]
+    fn test_example() {
+        let mut robot = HullRobot::new(scripted(&EXAMPLE), Color::Black);
+        let steps: Vec<Step> = robot.by_ref().collect();
+        assert_eq!(7, steps.len());
+        assert_eq!(
+            Step {
+                position: Point::new(-1, 0),
+                color: Color::Black,
+                direction: Direction::South,
+            },
+            steps[1]
+        );
+        assert_eq!(6, robot.painted_count());
+        assert_eq!(2, robot.visits(Point::default()));
+        assert_eq!(Point::new(0, -1), robot.position());
+        assert_eq!(Direction::West, robot.direction());
+        assert_eq!("  #\n  #\n## \n", robot.render());
+    }
+
+    #[test]
+    fn test_frames() {
+        let frames = HullRobot::new(scripted(&EXAMPLE), Color::Black).frames();
+        assert_eq!(8, frames.len());
+        assert_eq!("   \n ^ \n   \n", frames[0]);
+        assert_eq!("   \n<# \n   \n", frames[1]);
+        assert_eq!(" <#\n  #\n## \n", frames[7]);
+    }
+
+    #[test]
+    fn test_turn() {
+        let mut direction = Direction::North;
+        for &expected in &[Direction::East, Direction::South, Direction::West] {
+            direction = direction.turn(Turn::Right);
+            assert_eq!(expected, direction);
+        }
+        assert_eq!(Direction::North, direction.turn(Turn::Right));
+        assert_eq!(Direction::South, direction.turn(Turn::Left));
+    }
+}
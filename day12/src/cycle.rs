@@ -0,0 +1,152 @@
+use std::thread;
+
+use num::Integer;
+
+use crate::nbody::Moon;
+
+/// A sequence of states which repeats: the state after `start` steps comes back every `period`
+/// steps.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Cycle {
+  /// the number of steps before the cycle is entered (the pre-period)
+  pub start: u64,
+  pub period: u64,
+}
+
+impl Cycle {
+  /// The cycle of several independent sequences running side by side.
+  pub fn combine(cycles: &[Cycle]) -> Cycle {
+    cycles.iter().fold(Cycle { start: 0, period: 1 }, |acc, c| Cycle {
+      start: acc.start.max(c.start),
+      period: acc.period.lcm(&c.period),
+    })
+  }
+}
+
+/// Finds the cycle of the states reached from `initial` by repeatedly applying `step`, with
+/// Brent's algorithm. A repeat of any earlier state counts, not just of the initial one. Only
+/// two states are kept at any time.
+pub fn brent<T, F>(initial: &T, mut step: F) -> Cycle
+where
+  T: Clone + Eq,
+  F: FnMut(&mut T),
+{
+  // find the period: the tortoise waits at powers of two for the hare to catch up
+  let mut power = 1;
+  let mut period = 1;
+  let mut tortoise = initial.clone();
+  let mut hare = initial.clone();
+  step(&mut hare);
+  while tortoise != hare {
+    if power == period {
+      tortoise = hare.clone();
+      power *= 2;
+      period = 0;
+    }
+    step(&mut hare);
+    period += 1;
+  }
+
+  // find the start: with the hare one period ahead, both meet where the cycle begins
+  let mut tortoise = initial.clone();
+  let mut hare = initial.clone();
+  for _ in 0..period {
+    step(&mut hare);
+  }
+  let mut start = 0;
+  while tortoise != hare {
+    step(&mut tortoise);
+    step(&mut hare);
+    start += 1;
+  }
+  Cycle { start, period }
+}
+
+/// The moons along a single axis, stored as a struct of arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Axis {
+  pos: Vec<i64>,
+  vel: Vec<i64>,
+}
+
+impl Axis {
+  pub fn new<const D: usize>(moons: &[Moon<D>], axis: usize) -> Self {
+    Self {
+      pos: moons.iter().map(|m| m.pos[axis]).collect(),
+      vel: moons.iter().map(|m| m.vel[axis]).collect(),
+    }
+  }
+
+  pub fn progress_time(&mut self) {
+    let pos = &self.pos;
+    for (vel, &p) in self.vel.iter_mut().zip(pos.iter()) {
+      *vel += pos.iter().map(|&other| (other - p).signum()).sum::<i64>();
+    }
+    for (p, &v) in self.pos.iter_mut().zip(self.vel.iter()) {
+      *p += v;
+    }
+  }
+
+  pub fn cycle(&self) -> Cycle {
+    brent(self, Axis::progress_time)
+  }
+}
+
+/// The cycle of every axis. The axes do not influence each other, so each one is simulated on
+/// its own thread.
+pub fn axis_cycles<const D: usize>(moons: &[Moon<D>]) -> [Cycle; D] {
+  let mut cycles = [Cycle::default(); D];
+  thread::scope(|s| {
+    let handles: Vec<_> = (0..D)
+      .map(|axis| {
+        let system = Axis::new(moons, axis);
+        s.spawn(move || system.cycle())
+      })
+      .collect();
+    for (cycle, handle) in cycles.iter_mut().zip(handles) {
+      *cycle = handle.join().unwrap();
+    }
+  });
+  cycles
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::nbody::parse_moons;
+
+  #[test]
+  fn test_brent() {
+    // 0, 1, 2, 3, 4, 5, 3, 4, 5, ...
+    let cycle = brent(&0, |x| *x = if *x == 5 { 3 } else { *x + 1 });
+    assert_eq!(Cycle { start: 3, period: 3 }, cycle);
+
+    let cycle = brent(&0, |x| *x = (*x + 1) % 7);
+    assert_eq!(Cycle { start: 0, period: 7 }, cycle);
+
+    let cycle = brent(&1u64, |x| *x = (*x * *x + 1) % 255);
+    // 1, 2, 5, 26, 167, 95, 101, 2, ...
+    assert_eq!(Cycle { start: 1, period: 6 }, cycle);
+  }
+
+  #[test]
+  fn test_axis() {
+    let moons = parse_moons::<3>("<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>").unwrap();
+    let mut axis = Axis::new(&moons, 0);
+    axis.progress_time();
+    assert_eq!(vec![2, 3, 1, 2], axis.pos);
+    assert_eq!(vec![3, 1, -3, -1], axis.vel);
+
+    let cycles = axis_cycles(&moons);
+    let periods: Vec<u64> = cycles.iter().map(|c| c.period).collect();
+    assert_eq!(vec![18, 28, 44], periods);
+    assert!(cycles.iter().all(|c| c.start == 0));
+    assert_eq!(Cycle { start: 0, period: 2772 }, Cycle::combine(&cycles));
+  }
+
+  #[test]
+  fn test_combine() {
+    let cycles = [Cycle { start: 2, period: 4 }, Cycle { start: 5, period: 6 }];
+    assert_eq!(Cycle { start: 5, period: 12 }, Cycle::combine(&cycles));
+  }
+}
@@ -0,0 +1,385 @@
+use std::cmp::Ordering;
+
+use crate::cycle::{axis_cycles, Cycle};
+
+/// A body with a position and a velocity in `D` dimensions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Moon<const D: usize> {
+  pub pos: [i64; D],
+  pub vel: [i64; D],
+}
+
+impl<const D: usize> Moon<D> {
+  /// A moon at `pos` which does not move yet.
+  pub fn new(pos: [i64; D]) -> Self {
+    Self { pos, vel: [0; D] }
+  }
+
+  fn potential_energy(&self) -> u64 {
+    self.pos.iter().map(|p| p.unsigned_abs()).sum()
+  }
+
+  fn kinetic_energy(&self) -> u64 {
+    self.vel.iter().map(|v| v.unsigned_abs()).sum()
+  }
+
+  pub fn total_energy(&self) -> u64 {
+    self.potential_energy() * self.kinetic_energy()
+  }
+}
+
+/// The name of an axis: `x`, `y`, `z` and `w`, then `d4`, `d5`, ...
+pub fn axis_name(axis: usize) -> String {
+  match axis {
+    0 => "x".to_string(),
+    1 => "y".to_string(),
+    2 => "z".to_string(),
+    3 => "w".to_string(),
+    _ => format!("d{}", axis),
+  }
+}
+
+/// Parses one moon per line, like `<x=-1, y=0, z=2>`. The axes have to be named as by
+/// `axis_name`, in order.
+pub fn parse_moons<const D: usize>(s: &str) -> Result<Vec<Moon<D>>, String> {
+  let mut moons = Vec::new();
+  for (i, line) in s.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+    let invalid = |reason: String| format!("Invalid moon on line {}: {}", i + 1, reason);
+    let inner = line
+      .trim()
+      .strip_prefix('<')
+      .and_then(|l| l.strip_suffix('>'))
+      .ok_or_else(|| invalid(format!("{:?} is not enclosed in <>", line.trim())))?;
+    let parts: Vec<&str> = inner.split(',').map(|part| part.trim()).collect();
+    if parts.len() != D {
+      return Err(invalid(format!("expected {} coordinates, got {}", D, parts.len())));
+    }
+    let mut pos = [0; D];
+    for (axis, part) in parts.iter().enumerate() {
+      let name = axis_name(axis);
+      pos[axis] = part
+        .strip_prefix(&name)
+        .and_then(|p| p.strip_prefix('='))
+        .ok_or_else(|| invalid(format!("expected {}=..., got {:?}", name, part)))?
+        .parse()
+        .map_err(|e| invalid(format!("{:?}: {}", part, e)))?;
+    }
+    moons.push(Moon::new(pos));
+  }
+  Ok(moons)
+}
+
+/// Moons which pull each other together along every axis.
+#[derive(Debug, Clone)]
+pub struct Universe<const D: usize> {
+  pub moons: Vec<Moon<D>>,
+  /// total energy after every step, starting with the initial one; only kept on request
+  energy_history: Option<Vec<u64>>,
+}
+
+impl<const D: usize> Universe<D> {
+  pub fn new(moons: Vec<Moon<D>>) -> Self {
+    Self {
+      moons,
+      energy_history: None,
+    }
+  }
+
+  /// Keeps track of the total energy from now on.
+  pub fn record_energy(mut self) -> Self {
+    self.energy_history = Some(vec![self.total_energy()]);
+    self
+  }
+
+  /// The total energy after every step since `record_energy`.
+  pub fn energy_history(&self) -> &[u64] {
+    self.energy_history.as_deref().unwrap_or(&[])
+  }
+
+  fn compute_gravity(x1: i64, x2: i64) -> i64 {
+    match x1.cmp(&x2) {
+      Ordering::Less => 1,
+      Ordering::Equal => 0,
+      Ordering::Greater => -1,
+    }
+  }
+
+  pub fn apply_gravity(&mut self) {
+    /* To apply gravity, consider every pair of moons. On each axis, the
+     * velocity of each moon changes by exactly +1 or -1 to pull the moons
+     * together.
+     */
+    let n = self.moons.len();
+    for i in 0..n {
+      for j in i + 1..n {
+        for axis in 0..D {
+          let g = Self::compute_gravity(self.moons[i].pos[axis], self.moons[j].pos[axis]);
+          self.moons[i].vel[axis] += g;
+          self.moons[j].vel[axis] -= g;
+        }
+      }
+    }
+  }
+
+  pub fn apply_velocity(&mut self) {
+    for m in self.moons.iter_mut() {
+      for axis in 0..D {
+        m.pos[axis] += m.vel[axis];
+      }
+    }
+  }
+
+  pub fn progress_time(&mut self) {
+    self.apply_gravity();
+    self.apply_velocity();
+    if let Some(history) = &mut self.energy_history {
+      history.push(self.moons.iter().map(|m| m.total_energy()).sum());
+    }
+  }
+
+  pub fn total_energy(&self) -> u64 {
+    self.moons.iter().map(|m| m.total_energy()).sum()
+  }
+
+  /// The period and pre-period of every axis. The axes do not influence each other, so each
+  /// one has its own cycle.
+  pub fn axis_cycles(&self) -> [Cycle; D] {
+    axis_cycles(&self.moons)
+  }
+
+  /// When the moons start repeating their positions and velocities, and how often.
+  pub fn cycle(&self) -> Cycle {
+    let cycles = self.axis_cycles();
+    debug!("Axis cycles: {:?}", cycles);
+    Cycle::combine(&cycles)
+  }
+
+  /// The number of steps between two repeats of the same state of all moons.
+  pub fn cycle_length(&self) -> u64 {
+    self.cycle().period
+  }
+
+  /// Simulates `steps` steps and records the moons before and after every step.
+  pub fn trajectory(&mut self, steps: usize) -> Trajectory<D> {
+    let mut states = vec![self.moons.clone()];
+    for _ in 0..steps {
+      self.progress_time();
+      states.push(self.moons.clone());
+    }
+    Trajectory { states }
+  }
+}
+
+/// The moons at consecutive steps, starting with step 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory<const D: usize> {
+  pub states: Vec<Vec<Moon<D>>>,
+}
+
+impl<const D: usize> Trajectory<D> {
+  /// One line per moon and step, with the positions followed by the velocities.
+  pub fn to_csv(&self) -> String {
+    let mut header = vec!["step".to_string(), "moon".to_string()];
+    header.extend((0..D).map(axis_name));
+    header.extend((0..D).map(|axis| format!("v{}", axis_name(axis))));
+    let mut result = header.join(",");
+    result.push('\n');
+    for (step, moons) in self.states.iter().enumerate() {
+      for (i, moon) in moons.iter().enumerate() {
+        let values: Vec<String> = moon.pos.iter().chain(moon.vel.iter()).map(|v| v.to_string()).collect();
+        result.push_str(&format!("{},{},{}\n", step, i, values.join(",")));
+      }
+    }
+    result
+  }
+
+  /// An array with an object per step, holding the positions and velocities of all moons.
+  pub fn to_json(&self) -> String {
+    let list = |values: &[i64]| {
+      let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+      format!("[{}]", values.join(", "))
+    };
+    let steps: Vec<String> = self
+      .states
+      .iter()
+      .enumerate()
+      .map(|(step, moons)| {
+        let moons: Vec<String> = moons
+          .iter()
+          .map(|moon| format!("{{\"pos\": {}, \"vel\": {}}}", list(&moon.pos), list(&moon.vel)))
+          .collect();
+        format!("  {{\"step\": {}, \"moons\": [{}]}}", step, moons.join(", "))
+      })
+      .collect();
+    format!("[\n{}\n]\n", steps.join(",\n"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn init() {
+    let _ = env_logger::builder().is_test(true).try_init();
+  }
+
+  fn periods<const D: usize>(uni: &Universe<D>) -> Vec<u64> {
+    uni.axis_cycles().iter().map(|c| c.period).collect()
+  }
+
+  const EXAMPLE: &str = "<x=-1, y=0, z=2>
+<x=2, y=-10, z=-7>
+<x=4, y=-8, z=8>
+<x=3, y=5, z=-1>";
+
+  #[test]
+  fn test_parse_moons() {
+    let moons = parse_moons::<3>(EXAMPLE).unwrap();
+    assert_eq!(4, moons.len());
+    assert_eq!(Moon::new([2, -10, -7]), moons[1]);
+    assert_eq!(vec![Moon::new([1, 2])], parse_moons::<2>("<x=1, y=2>\n").unwrap());
+
+    let error = |s: &str| parse_moons::<3>(s).unwrap_err();
+    assert_eq!(
+      "Invalid moon on line 2: expected 3 coordinates, got 2",
+      error("<x=1, y=2, z=3>\n<x=1, y=2>")
+    );
+    assert_eq!(
+      "Invalid moon on line 1: expected y=..., got \"z=2\"",
+      error("<x=1, z=2, y=3>")
+    );
+    assert_eq!(
+      "Invalid moon on line 1: \"x=1, y=2, z=3\" is not enclosed in <>",
+      error("x=1, y=2, z=3")
+    );
+    assert_eq!(
+      "Invalid moon on line 1: \"z=a\": invalid digit found in string",
+      error("<x=1, y=2, z=a>")
+    );
+  }
+
+  #[test]
+  fn test_motion_and_energy() {
+    init();
+
+    let mut uni = Universe::new(parse_moons(EXAMPLE).unwrap());
+
+    uni.progress_time();
+
+    // after 1 step
+    assert_eq!(uni.moons[0].pos, [2, -1, 1]);
+    assert_eq!(uni.moons[0].vel, [3, -1, -1]);
+
+    assert_eq!(uni.moons[1].pos, [3, -7, -4]);
+    assert_eq!(uni.moons[1].vel, [1, 3, 3]);
+
+    assert_eq!(uni.moons[2].pos, [1, -7, 5]);
+    assert_eq!(uni.moons[2].vel, [-3, 1, -3]);
+
+    assert_eq!(uni.moons[3].pos, [2, 2, 0]);
+    assert_eq!(uni.moons[3].vel, [-1, -3, 1]);
+
+    uni.progress_time();
+
+    // after 2 steps
+    assert_eq!(uni.moons[0].pos, [5, -3, -1]);
+    assert_eq!(uni.moons[0].vel, [3, -2, -2]);
+
+    assert_eq!(uni.moons[1].pos, [1, -2, 2]);
+    assert_eq!(uni.moons[1].vel, [-2, 5, 6]);
+
+    assert_eq!(uni.moons[2].pos, [1, -4, -1]);
+    assert_eq!(uni.moons[2].vel, [0, 3, -6]);
+
+    assert_eq!(uni.moons[3].pos, [1, -4, 2]);
+    assert_eq!(uni.moons[3].vel, [-1, -6, 2]);
+
+    for _ in 2..10 {
+      uni.progress_time();
+    }
+
+    // after 10 steps
+    assert_eq!(uni.moons[0].pos, [2, 1, -3]);
+    assert_eq!(uni.moons[0].vel, [-3, -2, 1]);
+
+    assert_eq!(uni.moons[1].pos, [1, -8, 0]);
+    assert_eq!(uni.moons[1].vel, [-1, 1, 3]);
+
+    assert_eq!(uni.moons[2].pos, [3, -6, 1]);
+    assert_eq!(uni.moons[2].vel, [3, 2, -3]);
+
+    assert_eq!(uni.moons[3].pos, [2, 0, 4]);
+    assert_eq!(uni.moons[3].vel, [1, -1, -1]);
+
+    assert_eq!(uni.total_energy(), 179);
+  }
+
+  #[test]
+  fn test_energy_history() {
+    let mut uni = Universe::new(parse_moons::<3>(EXAMPLE).unwrap());
+    uni.progress_time();
+    assert!(uni.energy_history().is_empty());
+
+    let mut uni = Universe::new(parse_moons::<3>(EXAMPLE).unwrap()).record_energy();
+    for _ in 0..10 {
+      uni.progress_time();
+    }
+    let history = uni.energy_history();
+    assert_eq!(11, history.len());
+    // nothing moves at first
+    assert_eq!(0, history[0]);
+    assert_eq!(179, history[10]);
+  }
+
+  #[test]
+  fn test_cycle_length() {
+    init();
+
+    let uni = Universe::new(parse_moons::<3>(EXAMPLE).unwrap());
+    assert_eq!(vec![18, 28, 44], periods(&uni));
+    assert_eq!(uni.cycle_length(), 2772);
+
+    let uni = Universe::new(vec![
+      Moon::new([-8, -10, 0]),
+      Moon::new([5, 5, 10]),
+      Moon::new([2, -7, 3]),
+      Moon::new([9, -8, -3]),
+    ]);
+    assert_eq!(uni.cycle_length(), 4686774924);
+  }
+
+  #[test]
+  fn test_other_dimensions() {
+    // in one dimension, two moons oscillate around each other
+    let mut uni = Universe::new(vec![Moon::new([0]), Moon::new([3])]);
+    assert_eq!(vec![8], periods(&uni));
+    uni.progress_time();
+    assert_eq!(
+      [Moon { pos: [1], vel: [1] }, Moon { pos: [2], vel: [-1] }],
+      uni.moons[..]
+    );
+
+    // the 4th axis is independent of the others
+    let moons: Vec<Moon<4>> = parse_moons::<3>(EXAMPLE)
+      .unwrap()
+      .iter()
+      .map(|m| Moon::new([m.pos[0], m.pos[1], m.pos[2], m.pos[0]]))
+      .collect();
+    assert_eq!(vec![18, 28, 44, 18], periods(&Universe::new(moons)));
+  }
+
+  #[test]
+  fn test_export() {
+    let mut uni = Universe::new(vec![Moon::new([0, 1]), Moon::new([3, 1])]);
+    let trajectory = uni.trajectory(1);
+    assert_eq!(2, trajectory.states.len());
+    assert_eq!(
+      "step,moon,x,y,vx,vy\n0,0,0,1,0,0\n0,1,3,1,0,0\n1,0,1,1,1,0\n1,1,2,1,-1,0\n",
+      trajectory.to_csv()
+    );
+    assert_eq!(
+      "[\n  {\"step\": 0, \"moons\": [{\"pos\": [0, 1], \"vel\": [0, 0]}, {\"pos\": [3, 1], \"vel\": [0, 0]}]},\n  {\"step\": 1, \"moons\": [{\"pos\": [1, 1], \"vel\": [1, 0]}, {\"pos\": [2, 1], \"vel\": [-1, 0]}]}\n]\n",
+      trajectory.to_json()
+    );
+  }
+}
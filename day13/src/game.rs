@@ -0,0 +1,343 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use aoc2019::intcode::{IntcodeProgram, IntcodeResult};
+
+use crate::{process_output, GameState, Joystick, Tile};
+
+/// `Screen` keeps track of every tile drawn by the arcade cabinet so far; the program
+/// only sends the tiles which changed since the last frame.
+#[derive(Debug, Default, Clone)]
+pub struct Screen {
+    tiles: HashMap<(i64, i64), Tile>,
+    pub score: i64,
+    width: i64,
+    height: i64,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, game: &GameState) {
+        for item in game.items.iter() {
+            self.width = self.width.max(item.x + 1);
+            self.height = self.height.max(item.y + 1);
+            self.tiles.insert((item.x, item.y), item.tile);
+        }
+        if let Some(score) = game.score {
+            self.score = score;
+        }
+    }
+
+    pub fn find(&self, tile: Tile) -> Option<(i64, i64)> {
+        self.tiles
+            .iter()
+            .find(|(_, &t)| t == tile)
+            .map(|(&pos, _)| pos)
+    }
+
+    pub fn count(&self, tile: Tile) -> usize {
+        self.tiles.values().filter(|&&t| t == tile).count()
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Score: {}", self.score)?;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let tile = self.tiles.get(&(x, y)).unwrap_or(&Tile::EMPTY);
+                write!(f, "{}", tile)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// The arcade cabinet: the running program and everything it has drawn so far.
+#[derive(Debug, Clone)]
+pub struct Arcade {
+    program: IntcodeProgram,
+    pub screen: Screen,
+}
+
+impl Arcade {
+    pub fn new(mut code: Vec<i64>) -> Self {
+        code[0] = 2; // play for free
+        Self {
+            program: IntcodeProgram::new(code),
+            screen: Screen::new(),
+        }
+    }
+
+    /// Runs the program until it needs the next joystick move; `None` starts the game.
+    pub fn tick(&mut self, joystick: Option<Joystick>) -> IntcodeResult {
+        let input: Vec<i64> = joystick.iter().map(|j| j.value()).collect();
+        let mut output = Vec::new();
+        let status = self.program.run(&input, &mut output);
+        self.screen.update(&process_output(&mut output));
+        status
+    }
+}
+
+/// A `Controller` decides how to move the joystick after every frame.
+pub trait Controller {
+    /// Returns `None` to stop playing.
+    fn next_move(&mut self, arcade: &Arcade) -> Option<Joystick>;
+}
+
+fn move_towards(paddle_x: i64, x: i64) -> Joystick {
+    match paddle_x.cmp(&x) {
+        Ordering::Less => Joystick::Right,
+        Ordering::Equal => Joystick::Neutral,
+        Ordering::Greater => Joystick::Left,
+    }
+}
+
+/// Moves the paddle towards the ball.
+pub struct FollowBall;
+
+impl Controller for FollowBall {
+    fn next_move(&mut self, arcade: &Arcade) -> Option<Joystick> {
+        let ball = arcade.screen.find(Tile::BALL)?;
+        let paddle = arcade.screen.find(Tile::PADDLE)?;
+        Some(move_towards(paddle.0, ball.0))
+    }
+}
+
+/// Simulates the game on a snapshot of the arcade to find out where the ball is going to
+/// land on the paddle row, and moves the paddle there.
+#[derive(Debug, Default)]
+pub struct Predictor {
+    /// the predicted landing column and the number of ticks until the ball gets there
+    target: Option<(i64, usize)>,
+    last_ball: Option<(i64, i64)>,
+}
+
+impl Predictor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the column where the ball lands on the paddle row and the number of ticks
+    /// until it does.
+    pub fn predict(arcade: &Arcade, last_ball: Option<(i64, i64)>) -> Option<(i64, usize)> {
+        let paddle_row = arcade.screen.find(Tile::PADDLE)?.1;
+        let mut arcade = arcade.clone();
+        let mut prev = last_ball;
+        let mut current = arcade.screen.find(Tile::BALL)?;
+        let mut ticks = 0;
+        loop {
+            let status = arcade.tick(Some(Joystick::Neutral));
+            let next = arcade.screen.find(Tile::BALL)?;
+            ticks += 1;
+            // the ball was right above the paddle row and moving downwards
+            let moving_down = prev.map(|p| p.1 < current.1).unwrap_or(true);
+            if current.1 + 1 == paddle_row && moving_down {
+                return Some((current.0, ticks));
+            }
+            if !status.is_active() {
+                return None;
+            }
+            prev = Some(current);
+            current = next;
+        }
+    }
+}
+
+impl Controller for Predictor {
+    fn next_move(&mut self, arcade: &Arcade) -> Option<Joystick> {
+        let ball = arcade.screen.find(Tile::BALL)?;
+        let paddle = arcade.screen.find(Tile::PADDLE)?;
+        let last_ball = self.last_ball.replace(ball);
+        let target = match self.target {
+            Some((x, ticks)) if ticks > 0 => Some((x, ticks)),
+            _ => Self::predict(arcade, last_ball),
+        };
+        self.target = target.map(|(x, ticks)| (x, ticks - 1));
+        // without a target, the game is over before the ball comes back, so there is nothing
+        // to catch and moving would be wasted
+        Some(target.map_or(Joystick::Neutral, |(x, _)| move_towards(paddle.0, x)))
+    }
+}
+
+/// Replays a recorded sequence of joystick moves.
+pub struct Replay {
+    moves: std::vec::IntoIter<Joystick>,
+}
+
+impl Replay {
+    pub fn new(moves: Vec<Joystick>) -> Self {
+        Self {
+            moves: moves.into_iter(),
+        }
+    }
+}
+
+impl Controller for Replay {
+    fn next_move(&mut self, _arcade: &Arcade) -> Option<Joystick> {
+        self.moves.next()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GameResult {
+    pub score: i64,
+    pub blocks_left: usize,
+    /// every joystick move which was sent to the cabinet
+    pub moves: Vec<Joystick>,
+    /// the column of the paddle at the start, then every column where the ball came down to
+    /// the paddle row
+    pub landings: Vec<i64>,
+}
+
+impl GameResult {
+    /// number of game ticks, i.e. how often the cabinet asked for the joystick position
+    pub fn ticks(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// number of ticks in which the joystick was not neutral
+    pub fn joystick_moves(&self) -> usize {
+        self.moves
+            .iter()
+            .filter(|&&m| m != Joystick::Neutral)
+            .count()
+    }
+
+    /// The fewest joystick moves which get the paddle under every landing of the ball, one
+    /// column per move. The ball goes the same way whatever the paddle does in between, as long
+    /// as it is caught, so no controller which clears all blocks can do with fewer moves, and
+    /// all of them take the same number of ticks.
+    pub fn min_joystick_moves(&self) -> usize {
+        self.landings
+            .windows(2)
+            .map(|w| (w[1] - w[0]).unsigned_abs() as usize)
+            .sum()
+    }
+}
+
+/// Plays the game until it is over or the controller gives up. `observer` is called with
+/// every frame.
+pub fn play<C, F>(code: Vec<i64>, controller: &mut C, mut observer: F) -> GameResult
+where
+    C: Controller + ?Sized,
+    F: FnMut(&Screen),
+{
+    let mut arcade = Arcade::new(code);
+    let mut joystick = None;
+    let mut moves = Vec::new();
+    let mut landings = Vec::new();
+    let mut last_ball: Option<(i64, i64)> = None;
+    loop {
+        let status = arcade.tick(joystick);
+        observer(&arcade.screen);
+        if let (Some(ball), Some(paddle)) = (
+            arcade.screen.find(Tile::BALL),
+            arcade.screen.find(Tile::PADDLE),
+        ) {
+            if landings.is_empty() {
+                landings.push(paddle.0);
+            }
+            // the same landings as in `Predictor::predict`
+            if ball.1 + 1 == paddle.1 && last_ball.is_none_or(|b| b.1 < ball.1) {
+                landings.push(ball.0);
+            }
+            last_ball = Some(ball);
+        }
+        if !status.is_active() {
+            break;
+        }
+        match controller.next_move(&arcade) {
+            Some(j) => {
+                joystick = Some(j);
+                moves.push(j);
+            }
+            None => break,
+        }
+    }
+    GameResult {
+        score: arcade.screen.score,
+        blocks_left: arcade.screen.count(Tile::BLOCK),
+        moves,
+        landings,
+    }
+}
+
+/// Encodes joystick moves the same way the cabinet receives them, e.g. `0,-1,1`.
+pub fn encode_moves(moves: &[Joystick]) -> String {
+    moves
+        .iter()
+        .map(|m| m.value().to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+pub fn decode_moves(s: &str) -> Result<Vec<Joystick>, String> {
+    s.trim()
+        .split(',')
+        .filter(|x| !x.is_empty())
+        .map(|x| match x.trim() {
+            "0" => Ok(Joystick::Neutral),
+            "-1" => Ok(Joystick::Left),
+            "1" => Ok(Joystick::Right),
+            other => Err(format!("Invalid joystick move: {:?}", other)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_input;
+
+    #[test]
+    fn moves_roundtrip_test() {
+        let moves = vec![Joystick::Neutral, Joystick::Left, Joystick::Right];
+        assert_eq!("0,-1,1", encode_moves(&moves));
+        assert_eq!(Ok(moves), decode_moves("0,-1,1\n"));
+        assert!(decode_moves("0,2").is_err());
+    }
+
+    #[test]
+    fn replay_test() {
+        let recorded = play(read_input(), &mut FollowBall, |_| {});
+        assert_eq!(0, recorded.blocks_left);
+
+        let mut frames = 0;
+        let replayed = play(
+            read_input(),
+            &mut Replay::new(recorded.moves.clone()),
+            |_| frames += 1,
+        );
+        assert_eq!(recorded, replayed);
+        assert_eq!(recorded.moves.len() + 1, frames);
+    }
+
+    #[test]
+    fn predictor_test() {
+        let follow = play(read_input(), &mut FollowBall, |_| {});
+        let predictor = play(read_input(), &mut Predictor::new(), |_| {});
+        assert_eq!(0, predictor.blocks_left);
+        assert_eq!(follow.score, predictor.score);
+        assert_eq!(follow.ticks(), predictor.ticks());
+        assert!(predictor.joystick_moves() < follow.joystick_moves());
+        // the ball lands in the same places for both, and the predictor needs no extra moves
+        assert_eq!(follow.landings, predictor.landings);
+        assert_eq!(predictor.min_joystick_moves(), predictor.joystick_moves());
+    }
+
+    #[test]
+    fn screen_test() {
+        let mut screen = Screen::new();
+        screen.update(&process_output(&mut vec![
+            0, 0, 1, 1, 0, 4, 2, 1, 3, -1, 0, 7,
+        ]));
+        assert_eq!(Some((1, 0)), screen.find(Tile::BALL));
+        assert_eq!("Score: 7\n#o \n  -\n", screen.to_string());
+    }
+}
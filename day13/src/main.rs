@@ -3,11 +3,23 @@ extern crate log;
 extern crate aoc2019;
 extern crate env_logger;
 
-use std::cmp::Ordering;
+mod game;
+mod terminal;
+
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::time::Duration;
 
 use aoc2019::intcode::IntcodeProgram;
 
-#[derive(Debug, PartialEq)]
+use crate::game::{
+    decode_moves, encode_moves, play, Controller, FollowBall, Predictor, Replay, Screen,
+};
+use crate::terminal::{Keyboard, RawTerminal, Renderer};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum Tile {
     EMPTY,
     WALL,
@@ -42,7 +54,20 @@ impl Tile {
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Tile::EMPTY => ' ',
+            Tile::WALL => '#',
+            Tile::BLOCK => '=',
+            Tile::PADDLE => '-',
+            Tile::BALL => 'o',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum Joystick {
     Neutral,
     Left,
@@ -160,10 +185,117 @@ fn read_input() -> Vec<i64> {
     ];
 }
 
+/// Usage:
+///
+/// * `day13`: solve both parts
+/// * `day13 play [moves-file]`: play with the arrow keys, optionally recording the moves
+/// * `day13 ai [fps [follow|predict]]`: watch the AI play, following the ball (the default)
+///   or moving to where it will land
+/// * `day13 compare`: let both AI strategies play and compare how they did with the fewest
+///   ticks and joystick moves which clear all blocks
+/// * `day13 headless <frames-file> [moves-file]`: let the AI play without a terminal UI and
+///   record every frame
+/// * `day13 replay <moves-file> [fps]`: replay recorded moves
 fn main() {
     env_logger::init();
-    println!("Part one: {}", part_one(read_input()));
-    println!("Part two: {}", part_two(read_input()));
+
+    let args: Vec<String> = env::args().collect();
+    let fps = |idx: usize| -> Duration {
+        let fps: u32 = args
+            .get(idx)
+            .map(|s| s.parse().expect("invalid fps"))
+            .unwrap_or(30);
+        Duration::from_secs(1) / fps.max(1)
+    };
+
+    match args.get(1).map(|s| &s[..]) {
+        None => {
+            println!("Part one: {}", part_one(read_input()));
+            println!("Part two: {}", part_two(read_input()));
+        }
+        Some("play") => {
+            let tick = Duration::from_millis(150);
+            let moves = play_in_terminal(&mut Keyboard::new(tick), Duration::from_millis(0));
+            if let Some(fname) = args.get(2) {
+                fs::write(fname, encode_moves(&moves)).expect("Unable to write moves");
+            }
+        }
+        Some("ai") => {
+            let mut controller = strategy(args.get(3).map(|s| &s[..]).unwrap_or("follow"));
+            play_in_terminal(controller.as_mut(), fps(2));
+        }
+        Some("compare") => {
+            let mut cleared = None;
+            for name in &["follow", "predict"] {
+                let result = play(read_input(), strategy(name).as_mut(), |_| {});
+                println!(
+                    "{:>8}: score {}, blocks left {}, ticks {}, joystick moves {}",
+                    name,
+                    result.score,
+                    result.blocks_left,
+                    result.ticks(),
+                    result.joystick_moves()
+                );
+                if result.blocks_left == 0 {
+                    cleared = Some(result);
+                }
+            }
+            // every strategy which clears all blocks sees the same landings of the ball
+            if let Some(result) = cleared {
+                println!(
+                    "{:>8}: ticks {}, joystick moves {}",
+                    "minimum",
+                    result.ticks(),
+                    result.min_joystick_moves()
+                );
+            }
+        }
+        Some("headless") => {
+            let fname = args.get(2).expect("frames file missing");
+            let mut frames = BufWriter::new(File::create(fname).expect("Unable to create file"));
+            let result = play(read_input(), &mut FollowBall, |screen| {
+                writeln!(frames, "{}", screen).expect("Unable to write frame");
+            });
+            if let Some(fname) = args.get(3) {
+                fs::write(fname, encode_moves(&result.moves)).expect("Unable to write moves");
+            }
+            println!("Score: {}, moves: {}", result.score, result.moves.len());
+        }
+        Some("replay") => {
+            let fname = args.get(2).expect("moves file missing");
+            let data = fs::read_to_string(fname).expect("Unable to read moves");
+            let moves = decode_moves(&data).expect("Unable to parse moves");
+            play_in_terminal(&mut Replay::new(moves), fps(3));
+        }
+        Some(_) => panic!("invalid choice"),
+    }
+}
+
+fn strategy(name: &str) -> Box<dyn Controller> {
+    match name {
+        "follow" => Box::new(FollowBall),
+        "predict" => Box::new(Predictor::new()),
+        _ => panic!("invalid strategy: {}", name),
+    }
+}
+
+/// Plays the game in the terminal and returns the joystick moves.
+fn play_in_terminal<C: Controller + ?Sized>(
+    controller: &mut C,
+    frame_duration: Duration,
+) -> Vec<Joystick> {
+    let result = {
+        let _raw = RawTerminal::new().expect("Unable to set up terminal");
+        let mut renderer = Renderer::new(frame_duration);
+        play(read_input(), controller, |screen: &Screen| {
+            renderer.draw(screen).expect("Unable to draw frame");
+        })
+    };
+    println!(
+        "Game over. Score: {}, blocks left: {}",
+        result.score, result.blocks_left
+    );
+    result.moves
 }
 
 fn part_one(code: Vec<i64>) -> usize {
@@ -186,61 +318,29 @@ fn part_two_test() {
     assert_eq!(12954, part_two(read_input()));
 }
 
-fn part_two(mut code: Vec<i64>) -> i64 {
-    code[0] = 2; // play for free
-
-    let mut program = IntcodeProgram::new(code);
-    let mut output = Vec::new();
-    let mut input = vec![Joystick::Neutral.value()];
-    loop {
-        let status = program.run(&mut input, &mut output);
-        let game = process_output(&mut output);
-        let block_count = game.items.iter().filter(|a| a.tile == Tile::BLOCK).count();
-        debug!("Blocks left: {}", block_count);
-        if !status.is_active() {
-            println!("Blocks left: {}", block_count);
-            if let Some(score) = game.score {
-                println!("Game over");
-                return score;
-            }
-        }
-
-        let balls: Vec<&GameItem> = game.items.iter().filter(|a| a.tile == Tile::BALL).collect();
-        debug!("Balls: {:?}", balls);
-
-        let paddles: Vec<&GameItem> = game
-            .items
-            .iter()
-            .filter(|a| a.tile == Tile::PADDLE)
-            .collect();
-        debug!("Paddles: {:?}", paddles);
-        debug_assert_eq!(paddles.len(), 1);
-        let paddle = paddles[0];
+#[test]
+fn truncated_output_test() {
+    let state = process_output(&mut vec![1, 2, 3, -1, 0, 5, 7, 8]);
+    assert_eq!(1, state.items.len());
+    assert_eq!(Some(5), state.score);
+}
 
-        let mut lowest_ball = &balls[0];
-        for i in 1..balls.len() {
-            if balls[i].y < lowest_ball.y {
-                lowest_ball = &balls[i];
-            }
-        }
-        let joystick = match paddle.x.cmp(&lowest_ball.x) {
-            Ordering::Less => Joystick::Right,
-            Ordering::Equal => Joystick::Neutral,
-            Ordering::Greater => Joystick::Left,
-        };
-        input.clear();
-        input.push(joystick.value());
-        output.clear();
-    }
+fn part_two(code: Vec<i64>) -> i64 {
+    let result = play(code, &mut FollowBall, |_| {});
+    debug!("Blocks left: {}", result.blocks_left);
+    return result.score;
 }
 
 fn process_output(output: &mut Vec<i64>) -> GameState {
     let mut score = None;
     let mut items = Vec::new();
-    while !output.is_empty() {
-        let number = output.pop().expect("number");
-        let y = output.pop().expect("y coordinate");
-        let x = output.pop().expect("x coordinate");
+    // keep the order in which the cabinet sent the tiles: later tiles overwrite earlier ones
+    let triples = output.chunks_exact(3);
+    if !triples.remainder().is_empty() {
+        warn!("Ignoring truncated output: {:?}", triples.remainder());
+    }
+    for triple in triples {
+        let (x, y, number) = (triple[0], triple[1], triple[2]);
         if x == -1 && y == 0 {
             score = Some(number);
             debug!("Score: {}", number)
@@ -254,6 +354,7 @@ fn process_output(output: &mut Vec<i64>) -> GameState {
             items.push(item);
         }
     }
+    output.clear();
     return GameState {
         items: items,
         score: score,
@@ -0,0 +1,93 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::{cursor, execute, queue, terminal};
+
+use crate::game::{Arcade, Controller, Screen};
+use crate::Joystick;
+
+/// Puts the terminal into raw mode for as long as it is alive.
+pub struct RawTerminal;
+
+impl RawTerminal {
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Draws every frame in the terminal, waiting `frame_duration` between two frames.
+pub struct Renderer {
+    frame_duration: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl Renderer {
+    pub fn new(frame_duration: Duration) -> Self {
+        Self {
+            frame_duration,
+            last_frame: None,
+        }
+    }
+
+    pub fn draw(&mut self, screen: &Screen) -> io::Result<()> {
+        if let Some(last) = self.last_frame {
+            let elapsed = last.elapsed();
+            if elapsed < self.frame_duration {
+                thread::sleep(self.frame_duration - elapsed);
+            }
+        }
+        self.last_frame = Some(Instant::now());
+
+        let mut stdout = io::stdout();
+        queue!(stdout, cursor::MoveTo(0, 0))?;
+        // raw mode: a newline does not move the cursor back to the first column
+        for line in screen.to_string().lines() {
+            write!(stdout, "{}\r\n", line)?;
+        }
+        stdout.flush()
+    }
+}
+
+/// Lets a human play using the arrow keys; `q` or `Esc` quits.
+pub struct Keyboard {
+    tick: Duration,
+}
+
+impl Keyboard {
+    pub fn new(tick: Duration) -> Self {
+        Self { tick }
+    }
+}
+
+impl Controller for Keyboard {
+    fn next_move(&mut self, _arcade: &Arcade) -> Option<Joystick> {
+        let start = Instant::now();
+        let mut joystick = Joystick::Neutral;
+        // collect key presses until the tick is over; the last arrow key wins
+        while let Some(timeout) = self.tick.checked_sub(start.elapsed()) {
+            if !event::poll(timeout).ok()? {
+                break;
+            }
+            if let Event::Key(key) = event::read().ok()? {
+                match key.code {
+                    KeyCode::Left => joystick = Joystick::Left,
+                    KeyCode::Right => joystick = Joystick::Right,
+                    KeyCode::Char('q') | KeyCode::Esc => return None,
+                    _ => {}
+                }
+            }
+        }
+        Some(joystick)
+    }
+}
@@ -2,14 +2,19 @@
 extern crate log;
 extern crate env_logger;
 
-use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
 
+mod nanofactory;
+
+use nanofactory::{Nanofactory, FUEL, ORE};
+
 #[derive(Debug, PartialEq, Hash, Eq)]
 struct Ingredient<'a> {
   unit: &'a str,
-  amount: u64,
+  amount: u128,
 }
 
 impl<'a> Ingredient<'a> {
@@ -27,6 +32,12 @@ impl<'a> Ingredient<'a> {
   }
 }
 
+impl<'a> fmt::Display for Ingredient<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {}", self.amount, self.unit)
+  }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct Formula<'a> {
   input: Vec<Ingredient<'a>>,
@@ -34,27 +45,24 @@ struct Formula<'a> {
 }
 
 impl<'a> Formula<'a> {
-  pub fn produce_output(&self, amount: u64) -> (Vec<Ingredient>, u64) {
+  /// The ingredients needed for at least `amount` of the output, and how much more of the
+  /// output this yields. `None` if the numbers get too large.
+  pub fn produce_output(&self, amount: u128) -> Option<(Vec<Ingredient>, u128)> {
     let m = self.output.amount;
     let rem = amount % self.output.amount;
-    debug_assert_eq!(rem < m, true);
+    debug_assert!(rem < m);
     let leftover = if rem > 0 { m - rem } else { 0 };
-    let amount_adjusted = if rem > 0 { amount + leftover } else { amount };
+    let factor = amount / m + if rem > 0 { 1 } else { 0 };
 
-    let factor = amount_adjusted / m;
     let mut ingredients = Vec::new();
     for ing in self.input.iter() {
-      let ing_amount_needed = ing.amount * factor;
+      let ing_amount_needed = ing.amount.checked_mul(factor)?;
       ingredients.push(Ingredient {
         unit: ing.unit,
         amount: ing_amount_needed,
       })
     }
-    return (ingredients, leftover);
-  }
-
-  pub fn only_need_ore(&self) -> bool {
-    self.input.len() == 1 && self.input[0].unit == &"ORE"[..]
+    Some((ingredients, leftover))
   }
 
   pub fn parse(s: &str) -> Result<Formula, String> {
@@ -80,6 +88,13 @@ impl<'a> Formula<'a> {
   }
 }
 
+impl<'a> fmt::Display for Formula<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let input: Vec<String> = self.input.iter().map(|i| i.to_string()).collect();
+    write!(f, "{} => {}", input.join(", "), self.output)
+  }
+}
+
 fn parse_relations(s: &str) -> Result<Vec<Formula>, String> {
   let mut result = Vec::new();
   for line in s.split('\n') {
@@ -98,97 +113,25 @@ fn parse_relations(s: &str) -> Result<Vec<Formula>, String> {
   return Ok(result);
 }
 
-fn minimum_ore_for_fuel(relations: &Vec<Formula>, fuel_amount_desired: u64) -> u64 {
-  let mut formulas = HashMap::new();
-  for i in 0..relations.len() {
-    let r = &relations[i];
-    formulas.insert(r.output.unit, r);
-  }
-
-  let mut rdeps: HashMap<&str, u64> = HashMap::new();
-  let mut leftovers: HashMap<&str, u64> = HashMap::new();
-  rdeps.insert(&"FUEL"[..], fuel_amount_desired);
-
-  let mut update_found = true;
-  while update_found {
-    update_found = false;
-    let mut new_rdeps: HashMap<&str, u64> = HashMap::new();
-
-    for (unit, amount) in rdeps.iter() {
-      // we need 'amount' of 'unit'
-      let formula = &formulas.get(unit).expect("Formula not found");
-
-      if formula.only_need_ore() {
-        debug!("Not resolving dependency {} {} because it only depends on ORE", amount, unit);
-        let entry = new_rdeps.entry(unit).or_insert(0);
-        *entry = *entry + *amount;
-      } else {
-        update_found = true;
-        let mut amount_needed = *amount;
-        let leftover = *leftovers.get(unit).unwrap_or(&0);
-        if leftover > 0 {
-          debug!("Leftover available: {} {}", leftover, unit);
-          let usable_leftover = if leftover >= amount_needed { amount_needed } else { leftover };
-          debug!("Usable amount: {} {}", usable_leftover, unit);
-          amount_needed -= usable_leftover;
-          leftovers.insert(unit, leftover - usable_leftover);
-        }
-
-        debug!("Replacing {} {} using its formula", amount_needed, unit);
-        let (ingredients, leftover) = formula.produce_output(amount_needed);
-        if leftover > 0 {
-          debug!("Adding to leftovers: {} {}", leftover, unit);
-          let entry = leftovers.entry(unit).or_insert(0);
-          *entry = *entry + leftover;
-        }
+fn ore_to_fuel(factory: &Nanofactory, ore_available: u128) -> u128 {
+  factory.max_producible(FUEL, &[(ORE, ore_available)]).unwrap()
+}
 
-        debug!("Adding ingredients to new_rdeps");
-        for ingredient in ingredients {
-          let entry = new_rdeps.entry(ingredient.unit).or_insert(0);
-          debug!("Adding {} {}", ingredient.amount, ingredient.unit);
-          *entry = *entry + ingredient.amount;
+/// Reads budgets like `ORE=1000`.
+fn parse_budgets(args: &[String]) -> Result<Vec<(&str, u128)>, String> {
+  args
+    .iter()
+    .map(|arg| {
+      let parts: Vec<&str> = arg.split('=').collect();
+      match parts[..] {
+        [unit, amount] => {
+          let amount = amount.parse().map_err(|e| format!("Invalid budget {:?}: {}", arg, e))?;
+          Ok((unit, amount))
         }
+        _ => Err(format!("Expected a budget like ORE=1000, got {:?}", arg)),
       }
-    }
-    rdeps = new_rdeps;
-    debug!("rdeps: {:?}", rdeps);
-    debug!("leftovers: {:?}", leftovers);
-  }
-
-  debug!("rdeps: {:?}", rdeps);
-  debug!("leftovers: {:?}", leftovers);
-  let mut total_ore = 0;
-  for (unit, amount) in rdeps.iter() {
-    let formula = formulas.get(unit).expect("formula must exist");
-    let (ingredients, _) = formula.produce_output(*amount);
-    debug_assert_eq!(ingredients.len(), 1);
-    debug_assert_eq!(ingredients[0].unit, &"ORE"[..]);
-    let ore_amount = ingredients[0].amount;
-    debug!("Need {} {} => produced by {} ORE", amount, unit, ore_amount);
-    total_ore += ore_amount;
-  }
-  return total_ore;
-}
-
-fn ore_to_fuel(relations: &Vec<Formula>, ore_available: u64) -> u64 {
-  let mut lower = ore_available / minimum_ore_for_fuel(relations, 1);
-  let mut upper = 2 * lower; // this is an uneducated guess, let's see if it works
-  debug!("Guessing initial bounds: lower={}, upper={}", lower, upper);
-
-  while lower < upper {
-    let mid = (lower + upper + 1) / 2;
-    let ore = minimum_ore_for_fuel(relations, mid);
-    if ore > ore_available {
-      debug!("solution must be in lower half");
-      upper = mid - 1;
-    } else {
-      debug!("solution must be in upper half");
-      lower = mid;
-    }
-    debug!("New bounds: lower={}, upper={}", lower, upper);
-  }
-  debug_assert_eq!(lower, upper);
-  return upper;
+    })
+    .collect()
 }
 
 #[cfg(test)]
@@ -265,8 +208,8 @@ mod tests {
 4 C, 1 A => 1 CA
 2 AB, 3 BC, 4 CA => 1 FUEL";
 
-    let relations = parse_relations(&s[..]).unwrap();
-    let ore = minimum_ore_for_fuel(&relations, 1);
+    let factory = Nanofactory::parse(s).unwrap();
+    let ore = factory.minimum_ore_for_fuel(1);
     assert_eq!(ore, 165);
   }
 
@@ -284,8 +227,8 @@ mod tests {
 165 ORE => 2 GPVTF
 3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
 
-    let relations = parse_relations(&s[..]).unwrap();
-    let ore = minimum_ore_for_fuel(&relations, 1);
+    let factory = Nanofactory::parse(s).unwrap();
+    let ore = factory.minimum_ore_for_fuel(1);
     assert_eq!(ore, 13312);
   }
 
@@ -306,8 +249,8 @@ mod tests {
 1 VJHF, 6 MNCFX => 4 RFSQX
 176 ORE => 6 VJHF";
 
-    let relations = parse_relations(&s[..]).unwrap();
-    let ore = minimum_ore_for_fuel(&relations, 1);
+    let factory = Nanofactory::parse(s).unwrap();
+    let ore = factory.minimum_ore_for_fuel(1);
     assert_eq!(ore, 180697);
   }
 
@@ -333,8 +276,8 @@ mod tests {
 7 XCVML => 6 RJRHP
 5 BHXH, 4 VRPVC => 5 LTCX";
 
-    let relations = parse_relations(&s[..]).unwrap();
-    let ore = minimum_ore_for_fuel(&relations, 1);
+    let factory = Nanofactory::parse(s).unwrap();
+    let ore = factory.minimum_ore_for_fuel(1);
     assert_eq!(ore, 2210736);
   }
 
@@ -343,7 +286,7 @@ mod tests {
     init();
 
     let formula = Formula::parse(&"12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ"[..]).unwrap();
-    let (ingredients, leftover) = formula.produce_output(1);
+    let (ingredients, leftover) = formula.produce_output(1).unwrap();
     assert_eq!(leftover, 8);
     assert_eq!(
       ingredients,
@@ -363,7 +306,7 @@ mod tests {
       ]
     );
 
-    let (ingredients, leftover) = formula.produce_output(10);
+    let (ingredients, leftover) = formula.produce_output(10).unwrap();
     assert_eq!(leftover, 8);
     assert_eq!(
       ingredients,
@@ -389,7 +332,7 @@ mod tests {
     init();
 
     let formula = Formula::parse(&"44 XJWVT, 5 KHKGT => 1 FUEL"[..]).unwrap();
-    let (_ingredients, leftover) = formula.produce_output(1);
+    let (_ingredients, leftover) = formula.produce_output(1).unwrap();
     assert_eq!(leftover, 0);
   }
 
@@ -407,8 +350,8 @@ mod tests {
 165 ORE => 2 GPVTF
 3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT";
 
-    let relations = parse_relations(&s[..]).unwrap();
-    let fuel = ore_to_fuel(&relations, 1000000000000);
+    let factory = Nanofactory::parse(s).unwrap();
+    let fuel = ore_to_fuel(&factory, 1000000000000);
     assert_eq!(fuel, 82892753);
   }
 
@@ -429,8 +372,8 @@ mod tests {
 1 VJHF, 6 MNCFX => 4 RFSQX
 176 ORE => 6 VJHF";
 
-    let relations = parse_relations(&s[..]).unwrap();
-    let fuel = ore_to_fuel(&relations, 1000000000000);
+    let factory = Nanofactory::parse(s).unwrap();
+    let fuel = ore_to_fuel(&factory, 1000000000000);
     assert_eq!(fuel, 5586022);
   }
 
@@ -456,8 +399,8 @@ mod tests {
 7 XCVML => 6 RJRHP
 5 BHXH, 4 VRPVC => 5 LTCX";
 
-    let relations = parse_relations(&s[..]).unwrap();
-    let fuel = ore_to_fuel(&relations, 1000000000000);
+    let factory = Nanofactory::parse(s).unwrap();
+    let fuel = ore_to_fuel(&factory, 1000000000000);
     assert_eq!(fuel, 460664);
   }
 }
@@ -468,11 +411,43 @@ fn main() -> std::io::Result<()> {
   let mut contents = String::new();
   file.read_to_string(&mut contents)?;
 
-  let relations = parse_relations(&contents).expect("Unable to parse input file");
-  let total_ore = minimum_ore_for_fuel(&relations, 1);
+  // only ORE is raw here; the `max` mode builds its own factory from the budgets
+  let ore_factory =
+    || Nanofactory::parse(&contents).unwrap_or_else(|e| panic!("Invalid input: {}", e));
+  let args: Vec<String> = env::args().skip(1).collect();
+  match args.first().map(|arg| arg.as_str()) {
+    Some("plan") => {
+      // plan [TARGET [AMOUNT]]
+      let target = args.get(1).map_or(FUEL, |arg| arg.as_str());
+      let amount = args.get(2).map_or(Ok(1), |arg| arg.parse()).expect("Invalid amount");
+      match ore_factory().plan(target, amount) {
+        Ok(plan) => print!("{}", plan),
+        Err(e) => eprintln!("{}", e),
+      }
+      return Ok(());
+    }
+    Some("max") => {
+      // max TARGET RAW=BUDGET...
+      let target = args.get(1).map_or(FUEL, |arg| arg.as_str());
+      let budgets = parse_budgets(args.get(2..).unwrap_or(&[])).unwrap_or_else(|e| panic!("{}", e));
+      let raw_materials: Vec<&str> = budgets.iter().map(|&(unit, _)| unit).collect();
+      let result = parse_relations(&contents)
+        .and_then(|relations| Nanofactory::new(relations, &raw_materials))
+        .and_then(|factory| factory.max_producible(target, &budgets));
+      match result {
+        Ok(n) => println!("{} {}", n, target),
+        Err(e) => eprintln!("{}", e),
+      }
+      return Ok(());
+    }
+    _ => {}
+  }
+
+  let factory = ore_factory();
+  let total_ore = factory.minimum_ore_for_fuel(1);
   println!("Part One: {}", total_ore);
 
-  let fuel = ore_to_fuel(&relations, 1000000000000);
+  let fuel = ore_to_fuel(&factory, 1000000000000);
   println!("Part Two: {}", fuel);
 
   Ok(())
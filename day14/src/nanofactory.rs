@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::{parse_relations, Formula};
+
+pub const ORE: &str = "ORE";
+pub const FUEL: &str = "FUEL";
+
+/// All reactions of a nanofactory, checked and sorted so that every chemical comes before the
+/// chemicals it is made of.
+#[derive(Debug)]
+pub struct Nanofactory<'a> {
+  /// chemical -> the only reaction producing it
+  formulas: HashMap<&'a str, Formula<'a>>,
+  /// what is not produced, but has to be available
+  raw_materials: Vec<&'a str>,
+  /// every chemical after all chemicals which consume it
+  order: Vec<&'a str>,
+}
+
+impl<'a> Nanofactory<'a> {
+  /// The reactions in `s`, with ORE as the only raw material.
+  pub fn parse(s: &'a str) -> Result<Nanofactory<'a>, String> {
+    Self::new(parse_relations(s)?, &[ORE])
+  }
+
+  /// Fails if a chemical is produced by more than one reaction, if something besides the
+  /// `raw_materials` is not produced at all, or if chemicals are needed to produce themselves.
+  pub fn new(
+    relations: Vec<Formula<'a>>,
+    raw_materials: &[&'a str],
+  ) -> Result<Nanofactory<'a>, String> {
+    let mut formulas = HashMap::new();
+    for formula in relations {
+      let unit = formula.output.unit;
+      if raw_materials.contains(&unit) {
+        return Err(format!("{} is a raw material and cannot be produced", unit));
+      }
+      if formula.output.amount == 0 {
+        return Err(format!("The reaction for {} produces nothing", unit));
+      }
+      if formulas.insert(unit, formula).is_some() {
+        return Err(format!("More than one reaction produces {}", unit));
+      }
+    }
+    // sorted, so the same error is reported every time
+    let mut units: Vec<&str> = formulas.keys().copied().collect();
+    units.sort_unstable();
+    for &unit in &units {
+      for ingredient in &formulas[unit].input {
+        if !raw_materials.contains(&ingredient.unit) && !formulas.contains_key(ingredient.unit) {
+          return Err(format!(
+            "No reaction produces {} (needed for {})",
+            ingredient.unit, unit
+          ));
+        }
+      }
+    }
+
+    // Kahn's algorithm, starting with the chemicals nothing else is made of
+    let mut consumers: HashMap<&str, usize> = HashMap::new();
+    for formula in formulas.values() {
+      for ingredient in &formula.input {
+        *consumers.entry(ingredient.unit).or_insert(0) += 1;
+      }
+    }
+    let mut ready: Vec<&str> = units
+      .iter()
+      .copied()
+      .filter(|unit| !consumers.contains_key(unit))
+      .collect();
+    let mut order = Vec::new();
+    while let Some(unit) = ready.pop() {
+      order.push(unit);
+      if let Some(formula) = formulas.get(unit) {
+        for ingredient in &formula.input {
+          let count = consumers.get_mut(ingredient.unit).unwrap();
+          *count -= 1;
+          if *count == 0 {
+            ready.push(ingredient.unit);
+          }
+        }
+      }
+    }
+    let mut cyclic: Vec<&str> = consumers
+      .iter()
+      .filter(|&(unit, &count)| count > 0 && !raw_materials.contains(unit))
+      .map(|(&unit, _)| unit)
+      .collect();
+    if !cyclic.is_empty() {
+      cyclic.sort_unstable();
+      return Err(format!(
+        "Reactions form a cycle, so these cannot be ordered: {}",
+        cyclic.join(", ")
+      ));
+    }
+    for &raw in raw_materials {
+      if !order.contains(&raw) {
+        order.push(raw);
+      }
+    }
+    debug!("Reaction order: {:?}", order);
+
+    Ok(Nanofactory {
+      formulas,
+      raw_materials: raw_materials.to_vec(),
+      order,
+    })
+  }
+
+  fn check_target(&self, target: &str) -> Result<(), String> {
+    if self.formulas.contains_key(target) || self.raw_materials.contains(&target) {
+      Ok(())
+    } else {
+      Err(format!("No reaction produces {}", target))
+    }
+  }
+
+  /// How often every reaction has to run to produce `amount` of `target`, what is left over,
+  /// and how much of each raw material this takes. Every chemical is handled once, after
+  /// everything which consumes it.
+  pub fn plan(&self, target: &str, amount: u128) -> Result<Plan, String> {
+    self.check_target(target)?;
+    let overflow = || {
+      format!(
+        "Producing {} {} needs too much of something",
+        amount, target
+      )
+    };
+    let mut needed: HashMap<&str, u128> = HashMap::new();
+    needed.insert(target, amount);
+    let mut steps = Vec::new();
+    for &unit in &self.order {
+      let amount_needed = match needed.get(unit) {
+        Some(&n) if n > 0 => n,
+        _ => continue,
+      };
+      let formula = match self.formulas.get(unit) {
+        Some(formula) => formula,
+        None => continue,
+      };
+      let (ingredients, leftover) = formula.produce_output(amount_needed).ok_or_else(overflow)?;
+      for ingredient in ingredients {
+        let total = needed.entry(ingredient.unit).or_insert(0);
+        *total = total.checked_add(ingredient.amount).ok_or_else(overflow)?;
+      }
+      steps.push(Step {
+        formula: formula.to_string(),
+        runs: amount_needed / formula.output.amount + if leftover > 0 { 1 } else { 0 },
+        needed: amount_needed,
+        leftover,
+      });
+    }
+    let raw = self
+      .raw_materials
+      .iter()
+      .map(|&unit| (unit.to_string(), needed.get(unit).copied().unwrap_or(0)))
+      .collect();
+    Ok(Plan {
+      target: target.to_string(),
+      amount,
+      raw,
+      steps,
+    })
+  }
+
+  /// The least ORE needed to produce `amount` of `target`.
+  pub fn ore_for(&self, target: &str, amount: u128) -> Result<u128, String> {
+    Ok(
+      self
+        .plan(target, amount)?
+        .raw
+        .get(ORE)
+        .copied()
+        .unwrap_or(0),
+    )
+  }
+
+  pub fn minimum_ore_for_fuel(&self, fuel: u128) -> u128 {
+    self.ore_for(FUEL, fuel).unwrap()
+  }
+
+  /// Whether `amount` of `target` can be produced with the raw materials in `budgets`.
+  fn can_produce(&self, target: &str, amount: u128, budgets: &[(&str, u128)]) -> bool {
+    let plan = match self.plan(target, amount) {
+      Ok(plan) => plan,
+      // more of something than can be counted is more than any budget
+      Err(_) => return false,
+    };
+    plan.raw.iter().all(|(unit, &needed)| {
+      let budget = budgets
+        .iter()
+        .find(|(u, _)| u == unit)
+        .map_or(0, |&(_, b)| b);
+      needed <= budget
+    })
+  }
+
+  /// The most of `target` which can be produced with the raw materials in `budgets` (missing
+  /// ones are not available at all).
+  ///
+  /// Producing n times as much never needs more than n times the raw materials, so a lower
+  /// bound comes from the needs for one unit. From there, doubling finds an amount which
+  /// cannot be produced, and a binary search finds the answer between the two.
+  pub fn max_producible(&self, target: &str, budgets: &[(&str, u128)]) -> Result<u128, String> {
+    self.check_target(target)?;
+    if let Some(&(_, budget)) = budgets.iter().find(|(unit, _)| *unit == target) {
+      // a raw material is already there
+      return Ok(budget);
+    }
+    if !self.can_produce(target, 1, budgets) {
+      return Ok(0);
+    }
+    let for_one = self.plan(target, 1)?.raw;
+    let mut lower = for_one
+      .iter()
+      .filter(|(_, &needed)| needed > 0)
+      .map(|(unit, &needed)| budgets.iter().find(|(u, _)| u == unit).unwrap().1 / needed)
+      .min()
+      .unwrap_or(1)
+      .max(1);
+    let mut upper = lower;
+    while self.can_produce(target, upper, budgets) {
+      lower = upper;
+      upper = match upper.checked_mul(2) {
+        Some(upper) => upper,
+        None => return Ok(self.max_producible_in(target, budgets, lower, u128::MAX)),
+      };
+    }
+    debug!(
+      "{} can be produced {} times, but not {} times",
+      target, lower, upper
+    );
+    Ok(self.max_producible_in(target, budgets, lower, upper - 1))
+  }
+
+  /// Binary search for the most of `target` which can be produced, knowing that it is between
+  /// `lower` (which works) and `upper`.
+  fn max_producible_in(
+    &self,
+    target: &str,
+    budgets: &[(&str, u128)],
+    mut lower: u128,
+    mut upper: u128,
+  ) -> u128 {
+    while lower < upper {
+      let mid = lower + (upper - lower).div_ceil(2);
+      if self.can_produce(target, mid, budgets) {
+        lower = mid;
+      } else {
+        upper = mid - 1;
+      }
+    }
+    lower
+  }
+}
+
+/// One reaction in a production plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+  pub formula: String,
+  /// how often the reaction runs
+  pub runs: u128,
+  /// how much of its output is consumed
+  pub needed: u128,
+  /// how much of its output is not
+  pub leftover: u128,
+}
+
+/// How to produce some amount of a chemical.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+  pub target: String,
+  pub amount: u128,
+  /// how much of every raw material is used
+  pub raw: BTreeMap<String, u128>,
+  /// the reactions, each after all reactions consuming its output
+  pub steps: Vec<Step>,
+}
+
+impl fmt::Display for Plan {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let raw: Vec<String> = self
+      .raw
+      .iter()
+      .map(|(unit, amount)| format!("{} {}", amount, unit))
+      .collect();
+    writeln!(
+      f,
+      "{} {} needs {}:",
+      self.amount,
+      self.target,
+      raw.join(", ")
+    )?;
+    for step in &self.steps {
+      write!(f, "  {:>6} x {}", step.runs, step.formula)?;
+      if step.leftover > 0 {
+        write!(f, " ({} left over)", step.leftover)?;
+      }
+      writeln!(f)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SMALL: &str = "9 ORE => 2 A
+8 ORE => 3 B
+7 ORE => 5 C
+3 A, 4 B => 1 AB
+5 B, 7 C => 1 BC
+4 C, 1 A => 1 CA
+2 AB, 3 BC, 4 CA => 1 FUEL";
+
+  #[test]
+  fn test_validation() {
+    let error = |s: &str| Nanofactory::parse(s).unwrap_err();
+    assert_eq!(
+      "More than one reaction produces A",
+      error("1 ORE => 1 A\n2 ORE => 3 A")
+    );
+    assert_eq!(
+      "No reaction produces B (needed for A)",
+      error("1 B => 1 A\n1 A => 1 FUEL")
+    );
+    assert_eq!(
+      "ORE is a raw material and cannot be produced",
+      error("1 A => 1 ORE\n1 ORE => 1 A")
+    );
+    assert_eq!(
+      "Reactions form a cycle, so these cannot be ordered: A, B",
+      error("1 ORE, 1 B => 1 A\n1 A => 1 B\n1 A => 1 FUEL")
+    );
+    assert_eq!(
+      "The reaction for A produces nothing",
+      error("1 ORE => 0 A\n1 A => 1 FUEL")
+    );
+  }
+
+  #[test]
+  fn test_plan() {
+    let factory = Nanofactory::parse(SMALL).unwrap();
+    let plan = factory.plan(FUEL, 1).unwrap();
+    assert_eq!(Some(&165), plan.raw.get(ORE));
+    assert_eq!(
+      "1 FUEL needs 165 ORE:
+       1 x 2 AB, 3 BC, 4 CA => 1 FUEL
+       4 x 4 C, 1 A => 1 CA
+       3 x 5 B, 7 C => 1 BC
+       8 x 7 ORE => 5 C (3 left over)
+       2 x 3 A, 4 B => 1 AB
+       8 x 8 ORE => 3 B (1 left over)
+       5 x 9 ORE => 2 A
+",
+      plan.to_string()
+    );
+
+    assert_eq!(Ok(9), factory.ore_for("A", 1));
+    assert_eq!(Ok(5), factory.ore_for(ORE, 5));
+    assert_eq!(
+      Err("No reaction produces X".to_string()),
+      factory.ore_for("X", 1)
+    );
+  }
+
+  #[test]
+  fn test_raw_materials() {
+    let s = "2 ORE, 1 WATER => 3 A
+1 A, 2 WATER => 1 B
+1 A, 1 B => 1 FUEL";
+    let factory = Nanofactory::new(parse_relations(s).unwrap(), &[ORE, "WATER"]).unwrap();
+    let plan = factory.plan(FUEL, 3).unwrap();
+    // 6 A: 2 runs of the first reaction
+    assert_eq!(Some(&4), plan.raw.get(ORE));
+    assert_eq!(Some(&(2 + 6)), plan.raw.get("WATER"));
+    assert_eq!(
+      "3 FUEL needs 4 ORE, 8 WATER:",
+      plan.to_string().lines().next().unwrap()
+    );
+
+    assert_eq!(
+      Ok(3),
+      factory.max_producible(FUEL, &[(ORE, 4), ("WATER", 8)])
+    );
+    // water runs out first
+    assert_eq!(
+      Ok(2),
+      factory.max_producible(FUEL, &[(ORE, 100), ("WATER", 7)])
+    );
+    assert_eq!(Ok(0), factory.max_producible(FUEL, &[(ORE, 100)]));
+    assert_eq!(
+      Ok(7),
+      factory.max_producible("B", &[(ORE, 100), ("WATER", 17)])
+    );
+    assert_eq!(Ok(5), factory.max_producible("WATER", &[("WATER", 5)]));
+
+    assert_eq!(
+      "No reaction produces WATER (needed for A)",
+      Nanofactory::parse(s).unwrap_err()
+    );
+  }
+
+  #[test]
+  fn test_large_budgets() {
+    let factory = Nanofactory::parse("1 ORE => 100 FUEL").unwrap();
+    assert_eq!(
+      Ok(u128::MAX),
+      factory.max_producible(FUEL, &[(ORE, u128::MAX)])
+    );
+    let budget = 10u128.pow(30);
+    assert_eq!(
+      Ok(budget * 100),
+      factory.max_producible(FUEL, &[(ORE, budget)])
+    );
+
+    let factory = Nanofactory::parse("3 ORE => 1 A\n7 A => 2 FUEL").unwrap();
+    assert_eq!(
+      Ok(u128::MAX / 21 * 2),
+      factory.max_producible(FUEL, &[(ORE, u128::MAX / 21 * 21)])
+    );
+    assert!(factory.plan(FUEL, u128::MAX).is_err());
+  }
+}
@@ -3,13 +3,17 @@ extern crate aoc2019;
 #[macro_use]
 extern crate log;
 extern crate env_logger;
-extern crate im_rc;
 
-use im_rc::vector::Vector;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fs;
 
+use aoc2019::grid::Point;
 use aoc2019::intcode::IntcodeProgram;
 
+mod map;
+
+use map::{Map, Tile};
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 enum Direction {
     North,
@@ -19,6 +23,13 @@ enum Direction {
 }
 
 impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+    ];
+
     pub fn value(&self) -> i64 {
         match self {
             Direction::North => 1,
@@ -28,23 +39,13 @@ impl Direction {
         }
     }
 
-    pub fn from_points(src: &(i64, i64), dst: &(i64, i64)) -> Option<Direction> {
-        let dx = dst.0 - src.0;
-        let dy = dst.1 - src.1;
-        let direction = match (dx, dy) {
-            (0, -1) => Some(Direction::North),
-            (0, 1) => Some(Direction::South),
-            (1, 0) => Some(Direction::East),
-            (-1, 0) => Some(Direction::West),
-            _ => None,
-        };
-        trace!(
-            "Calculated direction from {:?} to {:?}: {:?}",
-            src,
-            dst,
-            direction
-        );
-        return direction;
+    pub fn delta(&self) -> Point {
+        match self {
+            Direction::North => Point::NORTH,
+            Direction::South => Point::SOUTH,
+            Direction::West => Point::WEST,
+            Direction::East => Point::EAST,
+        }
     }
 
     pub fn opposite(&self) -> Direction {
@@ -57,7 +58,7 @@ impl Direction {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum DroidResult {
     Blocked,
     Progress,
@@ -79,7 +80,7 @@ impl DroidResult {
 struct Droid {
     program: IntcodeProgram,
     path: Vec<Direction>,
-    pos: (i64, i64),
+    pos: Point,
 }
 
 impl Droid {
@@ -143,7 +144,7 @@ impl Droid {
         return Self {
             program,
             path: Vec::new(),
-            pos: (0, 0),
+            pos: Point::default(),
         };
     }
 
@@ -184,202 +185,78 @@ impl Droid {
         return result;
     }
 
-    fn update_position(&mut self, direction: Direction) {
-        let old_x = self.pos.0;
-        let old_y = self.pos.1;
-        let new_pos = match direction {
-            Direction::North => (old_x, old_y - 1),
-            Direction::South => (old_x, old_y + 1),
-            Direction::East => (old_x + 1, old_y),
-            Direction::West => (old_x - 1, old_y),
-        };
-        debug!(
-            "Droid moved {:?}: ({}, {}) -> {:?}",
-            direction, old_x, old_y, new_pos
-        );
-        self.pos = new_pos;
-    }
-}
-
-#[derive(Debug, PartialEq, Clone)]
-struct Node {
-    pos: (i64, i64),
-    dist: usize,
-}
-
-#[derive(Debug)]
-struct Graph {
-    map: HashMap<(i64, i64), HashSet<(i64, i64)>>,
-}
-
-#[derive(Debug, Clone)]
-struct Edge {
-    pos: (i64, i64),
-    path: Vector<(i64, i64)>,
-}
-
-impl Graph {
-    pub fn new() -> Self {
-        Self {
-            map: HashMap::new(),
-        }
-    }
-    pub fn find_path(&self, src: (i64, i64), dst: (i64, i64)) -> Vector<(i64, i64)> {
-        debug!("Need to find path from {:?} to {:?}", src, dst);
-
-        let mut stack: Vec<Edge> = Vec::new();
-        let mut discovered = HashSet::new();
-        let mut initial_path = Vector::new();
-        initial_path.push_back(src);
-        stack.push(Edge {
-            pos: src,
-            path: initial_path,
-        });
-
-        while let Some(edge) = stack.pop() {
-            debug!("Processing {:?}", edge);
-            if !discovered.contains(&edge.pos) {
-                discovered.insert(edge.pos);
-                if let Some(children) = self.map.get(&edge.pos) {
-                    debug!("Edge has children: {:?}", children);
-                    for child in children.iter() {
-                        debug!("Processing child: {:?}", child);
-                        let mut path = edge.path.clone();
-                        path.push_back(*child);
-                        if *child == dst {
-                            debug!("Found dst! path={:?}", path);
-                            return path;
-                        }
-                        stack.push(Edge {
-                            pos: *child,
-                            path: path,
-                        });
+    /// Visits every position reachable from the droid's, depth first, and records what it
+    /// finds. The droid ends up where it started.
+    pub fn explore(&mut self) -> Map {
+        let mut map = Map::new(self.pos);
+        loop {
+            let pos = self.pos;
+            let unknown = Direction::ALL
+                .iter()
+                .find(|d| map.get(pos + d.delta()).is_none());
+            match unknown {
+                Some(&direction) => match self.walk(direction) {
+                    DroidResult::Blocked => map.set(pos + direction.delta(), Tile::Wall),
+                    DroidResult::Progress => map.set(self.pos, Tile::Open),
+                    DroidResult::Final => map.set(self.pos, Tile::Oxygen),
+                },
+                None => {
+                    if !self.backtrack() {
+                        break;
                     }
                 }
             }
         }
-        return Vector::new();
+        info!("Explored the map:\n{}", map.render());
+        map
     }
 
-    pub fn upsert(&mut self, parent: (i64, i64), child: (i64, i64)) {
-        let children = self.map.entry(parent).or_insert(HashSet::new());
-        children.insert(child);
+    fn update_position(&mut self, direction: Direction) {
+        let new_pos = self.pos + direction.delta();
+        debug!("Droid moved {:?}: {} -> {}", direction, self.pos, new_pos);
+        self.pos = new_pos;
     }
 }
 
-fn solve() -> (usize, usize) {
-    let mut droid = Droid::new();
-
-    let mut graph = Graph::new();
-    let mut visited: HashSet<(i64, i64)> = HashSet::new();
-
-    // BFS algorithm
-    let mut queue: VecDeque<Node> = VecDeque::new();
-    queue.push_back(Node {
-        pos: (0, 0),
-        dist: 0,
-    });
-    visited.insert((0, 0));
-
-    let mut oxygen_pos = None;
-    let mut min_dist = std::usize::MAX;
-    while let Some(node) = queue.pop_front() {
-        debug!("*** Popped node: {:?}", node);
-        if droid.pos != node.pos {
-            debug!("Need to move droid from {:?} to {:?}", droid.pos, node.pos);
-            let path = graph.find_path(droid.pos, node.pos);
-            let mut prev = droid.pos;
-            for i in 1..path.len() {
-                let p = path[i];
-                let direction = Direction::from_points(&prev, &p).expect("Path must be routable");
-                let status = droid.walk(direction);
-                debug_assert_ne!(status, DroidResult::Blocked);
-                prev = p;
-            }
-        }
-
-        debug_assert_eq!(droid.pos, node.pos);
-        debug!("Droid exploring surrounding area");
-        for direction in &[
-            Direction::North,
-            Direction::South,
-            Direction::East,
-            Direction::West,
-        ] {
-            let status = droid.walk(*direction);
-            let dist = node.dist + 1;
-            match status {
-                DroidResult::Final | DroidResult::Progress => {
-                    graph.upsert(node.pos, droid.pos);
-                    if !visited.contains(&droid.pos) {
-                        visited.insert(droid.pos);
-                        queue.push_back(Node {
-                            pos: droid.pos,
-                            dist: dist,
-                        });
-                    }
-                    debug!("Reached target");
-                    if status == DroidResult::Final && dist < min_dist {
-                        min_dist = dist;
-                        oxygen_pos = Some(droid.pos);
-                    }
-                    droid.backtrack();
-                }
-                _ => {
-                    debug!("Blocked");
-                }
-            }
-        }
-        trace!("Queue: {:?}", queue);
-        trace!("Graph: {:?}", graph);
-    }
-
-    println!(
-        "Oxygen tank is located at {:?} and takes {} steps to reach",
-        oxygen_pos, min_dist
-    );
-    let part1 = min_dist;
-
-    info!("Visited: {:?}", visited);
-    info!("Starting oxygen simulation");
-    let mut oxygen_cells = Vector::new();
-    let mut steps = 0;
-
-    oxygen_cells.push_back(oxygen_pos.expect("Oxygen must exist"));
-    loop {
-        let mut new_cells = Vector::new();
-        for cell in oxygen_cells.iter() {
-            info!("Spreading oxygen from {:?}", cell);
-            let x = cell.0;
-            let y = cell.1;
-            for candidate in &[(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
-                if visited.remove(&candidate) {
-                    info!("Candidate filled with oxygen: {:?}", candidate);
-                    new_cells.push_back(*candidate);
-                }
-            }
-        }
-        if new_cells.is_empty() {
-            break;
-        }
-        oxygen_cells.append(new_cells);
-        steps += 1;
-    }
-    let part2 = steps;
-
-    return (part1, part2);
+fn solve(map: &Map) -> Result<(usize, usize), String> {
+    let part1 = map
+        .oxygen_distance()
+        .ok_or("The oxygen system is unreachable")?;
+    let part2 = map.fill_time().ok_or("There is no oxygen system")?;
+    Ok((part1, part2))
 }
 
 fn main() {
     env_logger::init();
-    let (part1, part2) = solve();
-    println!("Part One: {}", part1);
-    println!("Part Two: {}", part2);
+    let mode = env::args().nth(1);
+    let map = match mode.as_deref() {
+        // answer from a map saved with the `json` mode instead of exploring
+        Some("load") => {
+            let fname = env::args().nth(2).expect("Missing map file");
+            let data = fs::read_to_string(&fname).expect("Cannot read map file");
+            Map::from_json(&data).unwrap()
+        }
+        _ => Droid::new().explore(),
+    };
+    match mode.as_deref() {
+        Some("render") => print!("{}", map.render()),
+        Some("json") => println!("{}", map.to_json()),
+        _ => {
+            let (part1, part2) = solve(&map).unwrap();
+            println!("Part One: {}", part1);
+            println!("Part Two: {}", part2);
+            let (farthest, dist) = map.farthest_from(map.start()).unwrap();
+            println!("Farthest from the start: {} ({} steps)", farthest, dist);
+            println!("Dead ends: {}", map.dead_ends().len());
+        }
+    }
 }
 
 #[test]
 fn solve_test() {
-    let (part1, part2) = solve();
+    let map = Droid::new().explore();
+    let (part1, part2) = solve(&map).unwrap();
     assert_eq!(224, part1);
     assert_eq!(284, part2);
+    assert_eq!(Ok(map.clone()), Map::from_json(&map.to_json()));
 }
@@ -0,0 +1,226 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use aoc2019::grid::{Point, SparseGrid};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Tile {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+impl Tile {
+    pub fn from_char(c: char) -> Option<Tile> {
+        match c {
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Open),
+            'O' => Some(Tile::Oxygen),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Wall => '#',
+            Tile::Open => '.',
+            Tile::Oxygen => 'O',
+        }
+    }
+}
+
+/// The map as stored in JSON: the rows of the bounding box, drawn with the characters of
+/// `Tile` and a space for unknown positions.
+#[derive(Debug, Serialize, Deserialize)]
+struct MapFile {
+    /// the position of the first character of the first row
+    origin: (i64, i64),
+    start: (i64, i64),
+    rows: Vec<String>,
+}
+
+/// Everything the droid found out about the area, which is enough to answer any question about
+/// it without the droid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Map {
+    tiles: SparseGrid<Tile>,
+    start: Point,
+}
+
+impl Map {
+    pub fn new(start: Point) -> Self {
+        let mut tiles = SparseGrid::new();
+        tiles.set(start, Tile::Open);
+        Self { tiles, start }
+    }
+
+    pub fn set(&mut self, p: Point, tile: Tile) {
+        self.tiles.set(p, tile);
+    }
+
+    pub fn get(&self, p: Point) -> Option<Tile> {
+        self.tiles.get(p).copied()
+    }
+
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    pub fn oxygen(&self) -> Option<Point> {
+        self.tiles
+            .iter()
+            .find(|&(_, &t)| t == Tile::Oxygen)
+            .map(|(p, _)| p)
+    }
+
+    /// The number of steps from `from` to every reachable position.
+    pub fn distances(&self, from: Point) -> HashMap<Point, usize> {
+        self.tiles.bfs(from, |_, &t| t != Tile::Wall)
+    }
+
+    /// The fewest steps from the start to the oxygen system.
+    pub fn oxygen_distance(&self) -> Option<usize> {
+        let oxygen = self.oxygen()?;
+        self.distances(self.start).get(&oxygen).copied()
+    }
+
+    /// The position farthest away from `from`, and its distance.
+    pub fn farthest_from(&self, from: Point) -> Option<(Point, usize)> {
+        self.distances(from)
+            .into_iter()
+            .min_by_key(|&(p, d)| (Reverse(d), p))
+    }
+
+    /// The minutes until oxygen, spreading one step per minute, has filled the area.
+    pub fn fill_time(&self) -> Option<usize> {
+        self.farthest_from(self.oxygen()?).map(|(_, d)| d)
+    }
+
+    /// The open positions with only a single way out, sorted.
+    pub fn dead_ends(&self) -> Vec<Point> {
+        let open = |p: &Point| matches!(self.get(*p), Some(Tile::Open) | Some(Tile::Oxygen));
+        let mut result: Vec<Point> = self
+            .tiles
+            .points()
+            .filter(|p| open(p) && p.neighbours4().iter().filter(|n| open(n)).count() == 1)
+            .collect();
+        result.sort();
+        result
+    }
+
+    /// Draws the map with the droid's starting position as `D`.
+    pub fn render(&self) -> String {
+        let (grid, origin) = self.tiles.to_dense();
+        let start = self.start - origin;
+        let mut result = String::new();
+        for (y, row) in grid.rows().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                result.push(match tile {
+                    _ if Point::new(x as i64, y as i64) == start => 'D',
+                    Some(t) => t.to_char(),
+                    None => ' ',
+                });
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    pub fn to_json(&self) -> String {
+        let (grid, origin) = self.tiles.to_dense();
+        let file = MapFile {
+            origin: (origin.x, origin.y),
+            start: (self.start.x, self.start.y),
+            rows: grid
+                .rows()
+                .map(|row| row.iter().map(|t| t.map_or(' ', |t| t.to_char())).collect())
+                .collect(),
+        };
+        serde_json::to_string_pretty(&file).unwrap()
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let file: MapFile = serde_json::from_str(s).map_err(|e| format!("Invalid map: {}", e))?;
+        let origin = Point::from(file.origin);
+        let mut tiles = SparseGrid::new();
+        for (y, row) in file.rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let tile = Tile::from_char(c)
+                    .ok_or_else(|| format!("Invalid tile '{}' in row {}, column {}", c, y, x))?;
+                tiles.set(origin + Point::new(x as i64, y as i64), tile);
+            }
+        }
+        let start = Point::from(file.start);
+        match tiles.get(start) {
+            Some(Tile::Open) | Some(Tile::Oxygen) => Ok(Self { tiles, start }),
+            _ => Err(format!("The start {} is not an open tile", start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example of part two, with the droid placed in the top left corner.
+    fn example() -> Map {
+        let json = r##"{
+            "origin": [-1, -1],
+            "start": [0, 0],
+            "rows": [" ##   ", "#..## ", "#.#..#", "#.O.# ", " ###  "]
+        }"##;
+        Map::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_queries() {
+        let map = example();
+        assert_eq!(Some(Point::new(1, 2)), map.oxygen());
+        assert_eq!(Some(3), map.oxygen_distance());
+        assert_eq!(Some(4), map.fill_time());
+        assert_eq!(
+            Some((Point::new(1, 0), 4)),
+            map.farthest_from(Point::new(1, 2))
+        );
+        assert_eq!(vec![Point::new(1, 0), Point::new(3, 1)], map.dead_ends());
+    }
+
+    #[test]
+    fn test_render() {
+        let expected = " ##   \n#D.## \n#.#..#\n#.O.# \n ###  \n";
+        assert_eq!(expected, example().render());
+    }
+
+    #[test]
+    fn test_json() {
+        let map = example();
+        assert_eq!(Ok(map.clone()), Map::from_json(&map.to_json()));
+
+        let mut map = Map::new(Point::new(3, -2));
+        map.set(Point::new(4, -2), Tile::Wall);
+        map.set(Point::new(3, -1), Tile::Oxygen);
+        assert_eq!(Ok(map.clone()), Map::from_json(&map.to_json()));
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let invalid = r#"{"origin": [0, 0], "start": [0, 0], "rows": [".x"]}"#;
+        assert_eq!(
+            Err("Invalid tile 'x' in row 0, column 1".to_string()),
+            Map::from_json(invalid)
+        );
+        let walled = r##"{"origin": [0, 0], "start": [0, 0], "rows": ["#."]}"##;
+        assert_eq!(
+            Err("The start (0, 0) is not an open tile".to_string()),
+            Map::from_json(walled)
+        );
+        assert!(Map::from_json("[]")
+            .unwrap_err()
+            .starts_with("Invalid map: "));
+    }
+}
@@ -0,0 +1,395 @@
+use std::convert::TryFrom;
+use std::mem;
+use std::sync::Mutex;
+use std::thread;
+
+/// The base pattern of the puzzle.
+pub const BASE_PATTERN: [i64; 4] = [0, 1, 0, -1];
+
+/// Parses a signal of single digits.
+pub fn parse_signal(s: &str) -> Result<Vec<i32>, String> {
+  s.trim()
+    .chars()
+    .enumerate()
+    .map(|(i, c)| match c.to_digit(10) {
+      Some(d) => Ok(i32::try_from(d).unwrap()),
+      None => Err(format!("Invalid digit '{}' at position {}", c, i)),
+    })
+    .collect()
+}
+
+/// Packs digits into bytes for `Fft::par_run`.
+pub fn pack(signal: &[i32]) -> Vec<i8> {
+  signal.iter().map(|&d| d as i8).collect()
+}
+
+pub fn unpack(signal: &[i8]) -> Vec<i32> {
+  signal.iter().map(|&d| d as i32).collect()
+}
+
+/// Prefix sums of a signal, as read by `Fft::par_phase`.
+pub trait PrefixSums: Sync {
+  fn new(input: &[i8]) -> Self;
+
+  /// The sum of the first `i` digits.
+  fn get(&self, i: usize) -> i64;
+}
+
+/// Plain prefix sums, 4 bytes per digit.
+pub struct WidePrefix(Vec<u32>);
+
+impl PrefixSums for WidePrefix {
+  fn new(input: &[i8]) -> Self {
+    let mut prefix = Vec::with_capacity(input.len() + 1);
+    prefix.push(0u32);
+    for &d in input {
+      prefix.push(prefix.last().unwrap() + d as u32);
+    }
+    WidePrefix(prefix)
+  }
+
+  fn get(&self, i: usize) -> i64 {
+    self.0[i] as i64
+  }
+}
+
+/// The number of digits between two checkpoints of `PackedPrefix`. Their sum is at most
+/// `9 * 256`, which fits an `u16`.
+const CHECKPOINT_DISTANCE: usize = 256;
+
+/// Prefix sums in 2 bytes per digit: a `u32` checkpoint every `CHECKPOINT_DISTANCE` digits, and
+/// for every digit the `u16` sum since the last checkpoint. The checkpoints are few enough to
+/// stay in the cache, so a phase mostly reads the offsets, half of what `WidePrefix` needs.
+pub struct PackedPrefix {
+  checkpoints: Vec<u32>,
+  offsets: Vec<u16>,
+}
+
+impl PrefixSums for PackedPrefix {
+  fn new(input: &[i8]) -> Self {
+    let mut checkpoints = Vec::with_capacity(input.len() / CHECKPOINT_DISTANCE + 1);
+    let mut offsets = Vec::with_capacity(input.len() + 1);
+    let mut sum = 0u32;
+    for i in 0..=input.len() {
+      if i % CHECKPOINT_DISTANCE == 0 {
+        checkpoints.push(sum);
+      }
+      offsets.push((sum - checkpoints.last().unwrap()) as u16);
+      if i < input.len() {
+        sum += input[i] as u32;
+      }
+    }
+    Self {
+      checkpoints,
+      offsets,
+    }
+  }
+
+  #[inline]
+  fn get(&self, i: usize) -> i64 {
+    (self.checkpoints[i / CHECKPOINT_DISTANCE] + self.offsets[i] as u32) as i64
+  }
+}
+
+/// The number of threads for `Fft::par_phase`.
+pub fn default_threads() -> usize {
+  thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Reads the first `digits` digits of the signal as a number, e.g. the message offset.
+pub fn to_number(signal: &[i32], digits: usize) -> usize {
+  signal
+    .iter()
+    .take(digits)
+    .fold(0, |acc, &d| acc * 10 + d as usize)
+}
+
+/// Flawed Frequency Transmission with an arbitrary base pattern.
+///
+/// The pattern for output position `j` (0-based) repeats every value of the base pattern `j + 1`
+/// times and skips its very first value. The input therefore splits into blocks of `j + 1`
+/// digits sharing a coefficient, and each block is summed with a prefix sum. That is `n / (j + 1)`
+/// blocks per position and O(n log n) for a whole phase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fft {
+  pattern: Vec<i64>,
+}
+
+impl Default for Fft {
+  fn default() -> Self {
+    Self {
+      pattern: BASE_PATTERN.to_vec(),
+    }
+  }
+}
+
+impl Fft {
+  pub fn new(pattern: &[i64]) -> Result<Self, String> {
+    if pattern.is_empty() {
+      return Err("The base pattern must not be empty".to_string());
+    }
+    Ok(Self {
+      pattern: pattern.to_vec(),
+    })
+  }
+
+  /// The output digit for the pattern repeating every value `repeat` times, given the prefix
+  /// sums of `n` input digits.
+  fn digit<P: Fn(usize) -> i64>(&self, n: usize, prefix: P, repeat: usize) -> i64 {
+    let mut sum = 0;
+    // the first block is one shorter because the first value is skipped
+    let mut start = 0;
+    let mut end = repeat - 1;
+    for &coefficient in self.pattern.iter().cycle() {
+      if start >= n {
+        break;
+      }
+      if coefficient != 0 {
+        sum += coefficient * (prefix(end.min(n)) - prefix(start));
+      }
+      start = end;
+      end += repeat;
+    }
+    sum.abs() % 10
+  }
+
+  /// Computes one phase of the whole signal.
+  pub fn phase(&self, input: &[i32]) -> Vec<i32> {
+    let mut prefix = Vec::with_capacity(input.len() + 1);
+    prefix.push(0i64);
+    for &d in input {
+      prefix.push(prefix.last().unwrap() + d as i64);
+    }
+    (1..=input.len())
+      .map(|repeat| self.digit(input.len(), |i| prefix[i], repeat) as i32)
+      .collect()
+  }
+
+  /// Computes one phase of the whole signal on `threads` threads, with packed digits and
+  /// prefix sums `P`.
+  ///
+  /// Output position `j` costs `n / (j + 1)` blocks, so the threads take chunks of positions
+  /// from a queue, and the chunks grow with the position to keep their cost similar.
+  pub fn par_phase<P: PrefixSums>(&self, input: &[i8], threads: usize) -> Vec<i8> {
+    let n = input.len();
+    assert!(
+      n <= (u32::MAX / 9) as usize,
+      "Signal of length {} is too long",
+      n
+    );
+    let prefix = P::new(input);
+
+    let mut output = vec![0; n];
+    let queue = Mutex::new((0, &mut output[..]));
+    thread::scope(|s| {
+      for _ in 0..threads.max(1) {
+        s.spawn(|| loop {
+          let (start, chunk) = {
+            let mut queue = queue.lock().unwrap();
+            let (next, rest) = &mut *queue;
+            if rest.is_empty() {
+              break;
+            }
+            let size = (1 + *next / 64).min(rest.len());
+            let (chunk, tail) = mem::take(rest).split_at_mut(size);
+            *rest = tail;
+            *next += size;
+            (*next - size, chunk)
+          };
+          for (i, d) in chunk.iter_mut().enumerate() {
+            *d = self.digit(n, |i| prefix.get(i), start + i + 1) as i8;
+          }
+        });
+      }
+    });
+    output
+  }
+
+  pub fn run(&self, input: &[i32], phases: usize) -> Vec<i32> {
+    let mut signal = input.to_vec();
+    for phase in 1..=phases {
+      debug!("Phase {} of {}", phase, phases);
+      signal = self.phase(&signal);
+    }
+    signal
+  }
+
+  pub fn par_run(&self, input: &[i8], phases: usize, threads: usize) -> Vec<i8> {
+    self.par_run_with::<PackedPrefix>(input, phases, threads)
+  }
+
+  pub fn par_run_with<P: PrefixSums>(
+    &self,
+    input: &[i8],
+    phases: usize,
+    threads: usize,
+  ) -> Vec<i8> {
+    let mut signal = input.to_vec();
+    for phase in 1..=phases {
+      debug!("Phase {} of {} on {} threads", phase, phases, threads);
+      signal = self.par_phase::<P>(&signal, threads);
+    }
+    signal
+  }
+
+  /// Whether the digits from `offset` on only depend on the digits after them, in a signal of
+  /// length `n`. Then the first block (all digits before the position) has the coefficient 0,
+  /// and the second block reaches the end of the signal.
+  pub fn suffix_applies(&self, n: usize, offset: usize) -> bool {
+    self.pattern[0] == 0 && 2 * offset + 1 >= n
+  }
+
+  /// Computes one phase of the end of a signal, see `suffix_applies`. Each digit is the sum of
+  /// itself and all digits after it, times the coefficient of the second block.
+  pub fn suffix_phase(&self, tail: &mut [i32]) {
+    let coefficient = (self.pattern[1 % self.pattern.len()].abs() % 10) as i32;
+    let mut sum = 0;
+    for d in tail.iter_mut().rev() {
+      sum = (sum + *d) % 10;
+      *d = coefficient * sum % 10;
+    }
+  }
+
+  /// The `count` digits at `offset` after running `phases` phases on the signal repeated
+  /// `repeat` times. Only the end of the signal is computed if `suffix_applies`.
+  pub fn digits_at(
+    &self,
+    signal: &[i32],
+    repeat: usize,
+    phases: usize,
+    offset: usize,
+    count: usize,
+  ) -> Result<Vec<i32>, String> {
+    let n = signal.len() * repeat;
+    if offset + count > n {
+      return Err(format!(
+        "{} digits at offset {} exceed the signal of length {}",
+        count, offset, n
+      ));
+    }
+
+    if self.suffix_applies(n, offset) {
+      debug!("Using suffix sums from offset {} of {}", offset, n);
+      let mut tail: Vec<i32> = (offset..n).map(|i| signal[i % signal.len()]).collect();
+      for _ in 0..phases {
+        self.suffix_phase(&mut tail);
+      }
+      Ok(tail[..count].to_vec())
+    } else {
+      debug!("Using prefix sums for offset {} of {}", offset, n);
+      let input = pack(&signal.repeat(repeat));
+      let output = self.par_run(&input, phases, default_threads());
+      Ok(unpack(&output[offset..offset + count]))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_phase() {
+    let expected = vec![
+      vec![4, 8, 2, 2, 6, 1, 5, 8],
+      vec![3, 4, 0, 4, 0, 4, 3, 8],
+      vec![0, 3, 4, 1, 5, 5, 1, 8],
+      vec![0, 1, 0, 2, 9, 4, 9, 8],
+    ];
+    let fft = Fft::default();
+    let mut signal = parse_signal("12345678").unwrap();
+    for e in expected {
+      signal = fft.phase(&signal);
+      assert_eq!(e, signal);
+    }
+  }
+
+  #[test]
+  fn test_par_phase() {
+    let signal = parse_signal("59718730609456731351293131043954")
+      .unwrap()
+      .repeat(20);
+    for fft in [
+      Fft::default(),
+      Fft::new(&[1, 2]).unwrap(),
+      Fft::new(&[3, 0, -7]).unwrap(),
+    ] {
+      let expected = fft.run(&signal, 3);
+      for threads in 1..=4 {
+        assert_eq!(expected, unpack(&fft.par_run(&pack(&signal), 3, threads)));
+      }
+      let wide = fft.par_run_with::<WidePrefix>(&pack(&signal), 3, 2);
+      assert_eq!(expected, unpack(&wide));
+    }
+  }
+
+  #[test]
+  fn test_packed_prefix() {
+    let signal = pack(&parse_signal("9876543210").unwrap().repeat(100));
+    let wide = WidePrefix::new(&signal);
+    let packed = PackedPrefix::new(&signal);
+    assert_eq!(1001, packed.offsets.len());
+    assert_eq!(4, packed.checkpoints.len());
+    for i in 0..=signal.len() {
+      assert_eq!(wide.get(i), packed.get(i), "sum of {} digits", i);
+    }
+    assert_eq!(0, PackedPrefix::new(&[]).get(0));
+  }
+
+  #[test]
+  fn test_custom_pattern() {
+    let fft = Fft::new(&[1, 2]).unwrap();
+    assert_eq!(vec![4, 0, 7, 4, 0], fft.phase(&[1, 2, 3, 4, 5]));
+    assert!(!fft.suffix_applies(5, 4));
+    assert_eq!(
+      Err("The base pattern must not be empty".to_string()),
+      Fft::new(&[])
+    );
+  }
+
+  #[test]
+  fn test_digits_at() {
+    let fft = Fft::default();
+    let signal = parse_signal("80871224585914546619083218645595").unwrap();
+    assert_eq!(
+      Ok(parse_signal("24176176").unwrap()),
+      fft.digits_at(&signal, 1, 100, 0, 8)
+    );
+
+    // the first half needs prefix sums, the second half can use suffix sums
+    let signal = parse_signal("12345678").unwrap();
+    let full = fft.run(&signal.repeat(3), 10);
+    for offset in 0..20 {
+      assert_eq!(
+        Ok(full[offset..offset + 4].to_vec()),
+        fft.digits_at(&signal, 3, 10, offset, 4)
+      );
+    }
+    assert!(fft.suffix_applies(24, 12));
+    assert!(!fft.suffix_applies(24, 11));
+    assert_eq!(
+      Err("4 digits at offset 21 exceed the signal of length 24".to_string()),
+      fft.digits_at(&signal, 3, 10, 21, 4)
+    );
+  }
+
+  #[test]
+  fn test_suffix_phase() {
+    let expected = [[6, 1, 5, 8], [0, 4, 3, 8], [5, 5, 1, 8], [9, 4, 9, 8]];
+    let fft = Fft::default();
+    let mut signal = parse_signal("12345678").unwrap();
+    for e in expected.iter() {
+      fft.suffix_phase(&mut signal[4..]);
+      assert_eq!(e, &signal[4..]);
+    }
+  }
+
+  #[test]
+  fn test_parse_signal() {
+    assert_eq!(Ok(vec![1, 2, 3]), parse_signal("123\n"));
+    assert_eq!(
+      Err("Invalid digit 'x' at position 2".to_string()),
+      parse_signal("12x")
+    );
+  }
+}
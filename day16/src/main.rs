@@ -2,8 +2,14 @@
 extern crate log;
 extern crate env_logger;
 
+mod fft;
+
+use fft::{default_threads, pack, parse_signal, to_number, unpack, Fft, WidePrefix};
 use std::convert::TryFrom;
+use std::env;
+use std::time::Instant;
 
+// the naive implementation, see `fft` for the fast one
 fn mkpattern(pos: usize, len: usize) -> Vec<i8> {
   debug_assert_eq!(pos > 0, true);
   let base = [0, 1, 0, -1];
@@ -80,53 +86,59 @@ fn apply_pattern(src: &Vec<i32>, count: usize) -> Vec<i32> {
   return input;
 }
 
-fn fast_fft(input: &mut [i32], iterations: usize) {
-  let n = input.len();
-  debug!("iterations: {}, n={}", iterations, n);
-  let last = n - 1;
-  for _ in 1..=iterations {
-    for i in 1..=last {
-      let pos = last - i;
-      input[pos] = (input[pos + 1] + input[pos]).abs() % 10;
-    }
-  }
-  /* For i >= floor(n/2):
-   * output[end] = input[end]
-   * output[end - 1] = input[end] + input[end - 1]
-   * output[end - 2] = input[end] + input[end - 1] + input[end - 2]
-   */
+fn timed<T>(name: &str, f: impl FnOnce() -> T) -> T {
+  let start = Instant::now();
+  let result = f();
+  println!("{} (took {}ms)", name, start.elapsed().as_millis());
+  result
 }
 
-fn part_two(s: &str) -> i32 {
-  let prefix: Vec<i32> = s
-    .chars()
-    .map(|c| i32::try_from(c.to_digit(10).expect("Invalid digit")).unwrap())
-    .collect();
-
-  let mut offset = prefix[0];
-  for i in 1..=6 {
-    offset = offset * 10 + prefix[i];
-  }
-  let offset = offset as usize;
-
-  let factor = 10000;
-  let mut huge_s = String::with_capacity(s.len() * factor);
-  for _ in 0..factor {
-    huge_s.push_str(s);
-  }
+/// Compares the implementations on the real input: 650 digits and 10000 times as many.
+/// The naive one is quadratic, so it only runs on the short signal.
+fn bench(input: &[i32], phases: usize) {
+  let fft = Fft::default();
+  let threads = default_threads();
+  println!("650 digits, 100 phases, {} threads", threads);
+  let naive = timed("  naive", || apply_pattern(&input.to_vec(), 100));
+  let prefix = timed("  prefix sums", || fft.run(input, 100));
+  let packed = timed("  parallel, packed", || {
+    fft.par_run(&pack(input), 100, threads)
+  });
+  assert_eq!(naive, prefix);
+  assert_eq!(naive, unpack(&packed));
+
+  let large = input.repeat(10000);
+  println!(
+    "{} digits, {} phases, {} threads",
+    large.len(),
+    phases,
+    threads
+  );
+  let prefix = timed("  prefix sums", || fft.run(&large, phases));
+  let wide = timed("  parallel, packed digits, u32 prefix sums", || {
+    fft.par_run_with::<WidePrefix>(&pack(&large), phases, threads)
+  });
+  let packed = timed("  parallel, packed digits, u16 prefix sums", || {
+    fft.par_run(&pack(&large), phases, threads)
+  });
+  assert_eq!(prefix, unpack(&wide));
+  assert_eq!(prefix, unpack(&packed));
+  let offset = to_number(input, 7);
+  timed("  suffix sums, 100 phases from the message offset", || {
+    fft.digits_at(input, 10000, 100, offset, 8).unwrap()
+  });
+}
 
-  let mut real_input: Vec<i32> = huge_s
-    .chars()
-    .skip(offset)
-    .map(|c| i32::try_from(c.to_digit(10).expect("Invalid digit")).unwrap())
-    .collect();
-  fast_fft(real_input.as_mut(), 100);
+fn part_one(signal: &[i32]) -> String {
+  let digits = Fft::default().digits_at(signal, 1, 100, 0, 8).unwrap();
+  digits.iter().map(|d| d.to_string()).collect()
+}
 
-  let mut result = real_input[0];
-  for i in 1..=7 {
-    result = result * 10 + real_input[i];
-  }
-  return result;
+fn part_two(s: &str) -> Result<usize, String> {
+  let signal = parse_signal(s)?;
+  let offset = to_number(&signal, 7);
+  let digits = Fft::default().digits_at(&signal, 10000, 100, offset, 8)?;
+  Ok(to_number(&digits, 8))
 }
 
 #[cfg(test)]
@@ -162,30 +174,34 @@ mod tests {
   }
 
   #[test]
-  fn fast_fft_test() {
+  fn prefix_sums_test() {
     init();
-    let expected = vec![[6, 1, 5, 8], [0, 4, 3, 8], [5, 5, 1, 8], [9, 4, 9, 8]];
-    let src = &"12345678"[..];
-    let mut input = split_input(src);
-    let input = &mut input[4..];
-    debug!("Input: {:?}", input);
-    for i in 0..expected.len() {
-      fast_fft(input, 1);
-      assert_eq!(input, expected[i]);
+    let input = split_input("59718730609456731351293131043954");
+    let fft = Fft::default();
+    for phases in 1..=3 {
+      assert_eq!(apply_pattern(&input, phases), fft.run(&input, phases));
     }
+  }
 
-    let mut input = split_input(src);
-    let input = &mut input[4..];
-    fast_fft(input, 4);
-    assert_eq!(input, expected[3]);
+  #[test]
+  fn part_one_test() {
+    init();
+    assert_eq!(
+      part_one(&split_input("19617804207202209144916044189917")),
+      "73745418"
+    );
+    assert_eq!(
+      part_one(&split_input("69317163492948606335995924319873")),
+      "52432133"
+    );
   }
 
   #[test]
   fn part_two_test() {
     init();
-    assert_eq!(part_two(&"03036732577212944063491565474664"[..]), 84462026);
-    assert_eq!(part_two(&"02935109699940807407585447034323"[..]), 78725270);
-    assert_eq!(part_two(&"03081770884921959731165446850517"[..]), 53553731);
+    assert_eq!(part_two(&"03036732577212944063491565474664"[..]), Ok(84462026));
+    assert_eq!(part_two(&"02935109699940807407585447034323"[..]), Ok(78725270));
+    assert_eq!(part_two(&"03081770884921959731165446850517"[..]), Ok(53553731));
   }
 }
 
@@ -195,14 +211,29 @@ fn main() {
   let challenge_input = &"59718730609456731351293131043954182702121108074562978243742884161871544398977055503320958653307507508966449714414337735187580549358362555889812919496045724040642138706110661041990885362374435198119936583163910712480088609327792784217885605021161016819501165393890652993818130542242768441596060007838133531024988331598293657823801146846652173678159937295632636340994166521987674402071483406418370292035144241585262551324299766286455164775266890428904814988362921594953203336562273760946178800473700853809323954113201123479775212494228741821718730597221148998454224256326346654873824296052279974200167736410629219931381311353792034748731880630444730593"[..];
 
   let input = split_input(challenge_input);
-  let result = apply_pattern(&input, 100);
-  let prefix: Vec<i32> = result.iter().take(8).map(|x| *x).collect();
-  let mut prefix_str = String::with_capacity(prefix.len());
-  for d in prefix.iter() {
-    prefix_str.push_str(&d.to_string());
+  if env::args().nth(1).as_deref() == Some("pattern") {
+    // e.g. `pattern 1,0,-1`: the first 8 digits after 100 phases with another base pattern
+    let pattern: Vec<i64> = env::args()
+      .nth(2)
+      .expect("Missing base pattern")
+      .split(',')
+      .map(|v| v.trim().parse().expect("Invalid pattern value"))
+      .collect();
+    let fft = Fft::new(&pattern).unwrap();
+    let digits = fft.digits_at(&input, 1, 100, 0, 8).unwrap();
+    println!("{}", to_number(&digits, 8));
+    return;
+  }
+  if env::args().nth(1).as_deref() == Some("bench") {
+    // the phases on the long signal, which take seconds each
+    let phases = env::args()
+      .nth(2)
+      .map_or(1, |p| p.parse().expect("Invalid number of phases"));
+    bench(&input, phases);
+    return;
   }
-  println!("Part One: {:}", prefix_str);
+  println!("Part One: {:}", part_one(&input));
 
-  let result = part_two(challenge_input);
+  let result = part_two(challenge_input).unwrap();
   println!("Part Two: {:}", result);
 }
@@ -1,7 +1,9 @@
 mod compress;
 mod grid;
+mod video;
 
 use std::collections::HashSet;
+use std::env;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
@@ -11,8 +13,10 @@ use log::{debug, trace};
 use pretty_env_logger;
 
 use crate::compress::compress;
-use crate::grid::{Direction, Field, Grid};
+use crate::grid::{intersections, walk, Direction, Field, Grid};
+use crate::video::{FrameParser, Player};
 
+use aoc2019::grid::Point;
 use aoc2019::intcode::{IntcodeProgram, IntcodeResult};
 
 #[derive(Debug, PartialEq)]
@@ -41,7 +45,7 @@ impl Robot {
 /// While there are unvisited tiles:
 ///   walk in a straight line to the furthest node and continue from there
 fn greedy_path(grid: &Grid, robot: &mut Robot) -> Vec<String> {
-    let mut visited: HashSet<(i64, i64)> = HashSet::new();
+    let mut visited: HashSet<Point> = HashSet::new();
 
     debug!("start: {:?}", robot);
     let mut path = Vec::new();
@@ -55,29 +59,15 @@ fn greedy_path(grid: &Grid, robot: &mut Robot) -> Vec<String> {
         let mut direction = None;
         let mut max_dist = 0;
 
-        for (dir, p) in &[
-            (East, grid.walk_east(robot.x, robot.y)),
-            (West, grid.walk_west(robot.x, robot.y)),
-        ] {
-            if !visited.contains(p) {
-                let d = (robot.x - p.0).abs();
+        let pos = Point::new(robot.x, robot.y);
+        for &dir in &[East, West, North, South] {
+            let p = walk(grid, pos, dir);
+            if !visited.contains(&p) {
+                let d = p.manhattan(pos);
                 if d > max_dist {
                     max_dist = d;
-                    next = Some(*p);
-                    direction.replace(dir.clone());
-                }
-            }
-        }
-        for (dir, p) in &[
-            (North, grid.walk_north(robot.x, robot.y)),
-            (South, grid.walk_south(robot.x, robot.y)),
-        ] {
-            if !visited.contains(p) {
-                let d = (robot.y - p.1).abs();
-                if d > max_dist {
-                    max_dist = d;
-                    next = Some(*p);
-                    direction.replace(dir.clone());
+                    next = Some(p);
+                    direction.replace(dir);
                 }
             }
         }
@@ -94,8 +84,8 @@ fn greedy_path(grid: &Grid, robot: &mut Robot) -> Vec<String> {
                 let orient = robot.rotate(direction.unwrap()).unwrap();
                 path.push(String::from(orient));
                 path.push(max_dist.to_string());
-                robot.x = n.0;
-                robot.y = n.1;
+                robot.x = n.x;
+                robot.y = n.y;
             }
             None => {
                 go = false;
@@ -119,56 +109,27 @@ fn read_input(fname: &str) -> Vec<i64> {
 }
 
 fn parse_ascii(src: Vec<i64>) -> (Robot, Grid) {
-    let mut grid: Vec<Vec<Field>> = Vec::new();
-    let mut row: Vec<Field> = Vec::new();
-
+    let picture: String = src.iter().map(|&d| char::from(d as u8)).collect();
     let mut robot = None;
-    let mut x = 0;
-    let mut y = 0;
-    for d in src.iter() {
-        let ch = char::from(*d as u8);
-        match ch {
-            '\n' => {
-                grid.push(row);
-                row = Vec::new();
-                x = 0;
-                y += 1;
-                continue;
-            }
-            '#' => {
-                row.push(Field::Tile);
-            }
-            '.' => {
-                row.push(Field::Empty);
-            }
-            c => {
-                row.push(Field::Tile);
-                robot = Some(Robot {
-                    x,
-                    y,
-                    direction: match c {
-                        '^' => Direction::North,
-                        'v' => Direction::South,
-                        '>' => Direction::East,
-                        '<' => Direction::West,
-                        _ => panic!("Unexpected ASCII char"),
-                    },
-                });
-            }
+    let grid = Grid::parse(picture.trim_end(), |p, c| match c {
+        '#' => Field::Tile,
+        '.' | ' ' => Field::Empty,
+        c => {
+            robot = Some(Robot {
+                x: p.x,
+                y: p.y,
+                direction: match c {
+                    '^' => Direction::North,
+                    'v' => Direction::South,
+                    '>' => Direction::East,
+                    '<' => Direction::West,
+                    _ => panic!("Unexpected ASCII char"),
+                },
+            });
+            Field::Tile
         }
-        x += 1;
-    }
-
-    let height = grid.len();
-    let width = grid[0].len();
-    return (
-        robot.unwrap(),
-        Grid {
-            fields: grid,
-            width,
-            height,
-        },
-    );
+    });
+    return (robot.unwrap(), grid);
 }
 
 fn build(code: Vec<i64>) -> (Robot, Grid) {
@@ -183,7 +144,10 @@ fn part1() -> usize {
     let (robot, grid) = build(code);
     debug!("Grid:\n{:}", grid);
     debug!("Robot: {:?}", robot);
-    return grid.intersections().iter().map(|x| x.0 * x.1).sum();
+    return intersections(&grid)
+        .iter()
+        .map(|p| (p.x * p.y) as usize)
+        .sum();
 }
 
 fn to_opcode_input(prog: &Vec<String>) -> Vec<i64> {
@@ -201,7 +165,9 @@ fn to_opcode_input(prog: &Vec<String>) -> Vec<i64> {
     return result;
 }
 
-fn part2() -> i64 {
+/// Runs the vacuum robot; if a `player` is given, the continuous video feed is enabled
+/// and every frame is shown while the robot moves.
+fn part2(mut player: Option<Player>) -> i64 {
     let mut code = read_input(&"input.txt"[..]);
     let (mut robot, grid) = build(code.clone());
     let path = greedy_path(&grid, &mut robot);
@@ -213,7 +179,8 @@ fn part2() -> i64 {
     let mut prog_a = to_opcode_input(&compressed.prog_a);
     let mut prog_b = to_opcode_input(&compressed.prog_b);
     let mut prog_c = to_opcode_input(&compressed.prog_c);
-    let mut visual_feed = vec![('n' as u8) as i64, 10];
+    let feed = if player.is_some() { 'y' } else { 'n' };
+    let mut visual_feed = vec![(feed as u8) as i64, 10];
 
     input_src.append(&mut prog_a);
     input_src.append(&mut prog_b);
@@ -234,7 +201,16 @@ fn part2() -> i64 {
             .collect::<Vec<String>>()
             .join(",")
     );
-    let status = program.run(&input_src, &mut output);
+    let mut parser = FrameParser::new();
+    let status = program.run_with(&input_src, |n| {
+        output.push(n);
+        if let Some(player) = &mut player {
+            if let Some(frame) = parser.push(n) {
+                let (robot, grid) = parse_ascii(frame);
+                player.show(&robot, &grid).expect("Failed to show frame");
+            }
+        }
+    });
     debug_assert_eq!(IntcodeResult::TERMINATED, status);
 
     debug!(
@@ -255,11 +231,27 @@ fn part2() -> i64 {
     return result[0];
 }
 
+/// Usage: `day17 [y [fps [asciicast-file]]]`
+///
+/// Passing `y` answers the robot's "Continuous video feed?" prompt with yes and plays the feed.
 fn main() {
     pretty_env_logger::init();
+
+    let player = match env::args().nth(1) {
+        Some(ref arg) if arg == "y" => {
+            let fps = env::args()
+                .nth(2)
+                .map(|s| s.parse().expect("invalid fps"))
+                .unwrap_or(30);
+            Some(Player::new(fps, env::args().nth(3)))
+        }
+        Some(_) => panic!("invalid choice"),
+        None => None,
+    };
+
     let start = Instant::now();
     let p1 = part1();
-    let p2 = part2();
+    let p2 = part2(player);
     let elapsed = start.elapsed();
     println!(
         "Part One: {}\n\
@@ -278,7 +270,7 @@ fn part1_test() {
 
 #[test]
 fn part2_test() {
-    assert_eq!(862452, part2());
+    assert_eq!(862452, part2(None));
 }
 
 #[test]
@@ -0,0 +1,227 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{self, BufWriter};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::grid::{Direction, Grid};
+use crate::Robot;
+
+// moves the cursor to the top-left corner of the terminal
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const HIGHLIGHT: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// `FrameParser` collects the ASCII output of the vacuum robot and cuts it into frames.
+///
+/// A frame is a block of map lines which is terminated by an empty line. Everything else
+/// (prompts like `Main:` or the final amount of space dust) is dropped.
+#[derive(Debug, Default)]
+pub struct FrameParser {
+    frame: Vec<i64>,
+    line: Vec<i64>,
+}
+
+impl FrameParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds a single output number to the parser. Returns a complete frame
+    /// (suitable for `parse_ascii`) as soon as one is available.
+    pub fn push(&mut self, n: i64) -> Option<Vec<i64>> {
+        if n >= 128 {
+            // space dust, not part of the video feed
+            return None;
+        }
+        if n != '\n' as i64 {
+            self.line.push(n);
+            return None;
+        }
+
+        if self.line.is_empty() {
+            if self.frame.is_empty() {
+                return None;
+            }
+            return Some(std::mem::take(&mut self.frame));
+        }
+
+        let line = std::mem::take(&mut self.line);
+        if line.iter().all(|&c| is_map_char(c)) {
+            self.frame.extend(line);
+            self.frame.push('\n' as i64);
+        } else {
+            // a prompt; whatever we have collected so far is not a frame
+            self.frame.clear();
+        }
+        None
+    }
+}
+
+fn is_map_char(c: i64) -> bool {
+    matches!(char::from(c as u8), '#' | '.' | '^' | 'v' | '<' | '>')
+}
+
+/// Renders the grid and highlights the robot's position and direction.
+pub fn render_frame(robot: &Robot, grid: &Grid) -> String {
+    let arrow = match robot.direction {
+        Direction::North => '^',
+        Direction::South => 'v',
+        Direction::East => '>',
+        Direction::West => '<',
+    };
+    let mut result = String::new();
+    for (y, line) in grid.to_string().lines().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            if x as i64 == robot.x && y as i64 == robot.y {
+                result.push_str(HIGHLIGHT);
+                result.push(arrow);
+                result.push_str(RESET);
+            } else {
+                result.push(c);
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Writes frames as an [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) recording.
+pub struct Asciicast<W: Write> {
+    out: W,
+    start: Instant,
+}
+
+impl<W: Write> Asciicast<W> {
+    pub fn new(mut out: W, width: usize, height: usize) -> io::Result<Self> {
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}}}",
+            width, height
+        )?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn write_event(&mut self, data: &str) -> io::Result<()> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        writeln!(
+            self.out,
+            "[{:.6}, \"o\", \"{}\"]",
+            elapsed,
+            escape_json(data)
+        )
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\r\\n"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Plays the video feed in the terminal, optionally recording it to an asciicast file.
+pub struct Player {
+    frame_duration: Duration,
+    last_frame: Option<Instant>,
+    cast: Option<Asciicast<BufWriter<File>>>,
+    cast_fname: Option<String>,
+    frame_count: usize,
+}
+
+impl Player {
+    pub fn new(fps: u32, cast_fname: Option<String>) -> Self {
+        Self {
+            frame_duration: Duration::from_secs(1) / fps.max(1),
+            last_frame: None,
+            cast: None,
+            cast_fname,
+            frame_count: 0,
+        }
+    }
+
+    pub fn show(&mut self, robot: &Robot, grid: &Grid) -> io::Result<()> {
+        let mut screen = String::new();
+        if self.frame_count == 0 {
+            screen.push_str(CLEAR_SCREEN);
+        }
+        screen.push_str(CURSOR_HOME);
+        screen.push_str(&render_frame(robot, grid));
+
+        if self.cast.is_none() {
+            if let Some(fname) = &self.cast_fname {
+                let out = BufWriter::new(File::create(fname)?);
+                self.cast = Some(Asciicast::new(out, grid.width(), grid.height())?);
+            }
+        }
+        if let Some(cast) = &mut self.cast {
+            cast.write_event(&screen)?;
+        }
+
+        // frame pacing
+        if let Some(last) = self.last_frame {
+            let elapsed = last.elapsed();
+            if elapsed < self.frame_duration {
+                thread::sleep(self.frame_duration - elapsed);
+            }
+        }
+        self.last_frame = Some(Instant::now());
+        self.frame_count += 1;
+
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        handle.write_all(screen.as_bytes())?;
+        handle.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_ascii(s: &str) -> Vec<i64> {
+        s.chars().map(|c| c as i64).collect()
+    }
+
+    #[test]
+    fn frame_parser_test() {
+        let mut parser = FrameParser::new();
+        let mut frames = Vec::new();
+        for n in to_ascii("..#..\n..^..\n\nMain:\nContinuous video feed?\n\n#.\n>#\n\n")
+            .into_iter()
+            .chain(std::iter::once(862452))
+        {
+            if let Some(frame) = parser.push(n) {
+                frames.push(frame);
+            }
+        }
+        assert_eq!(
+            vec![to_ascii("..#..\n..^..\n"), to_ascii("#.\n>#\n")],
+            frames
+        );
+    }
+
+    #[test]
+    fn asciicast_test() {
+        let mut out = Vec::new();
+        {
+            let mut cast = Asciicast::new(&mut out, 3, 1).unwrap();
+            cast.write_event("\x1b[H#\"\n").unwrap();
+        }
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!("{\"version\": 2, \"width\": 3, \"height\": 1}", lines[0]);
+        assert!(lines[1].ends_with(", \"o\", \"\\u001b[H#\\\"\\r\\n\"]"));
+    }
+}
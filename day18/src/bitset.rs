@@ -1,47 +1,52 @@
 use bitset_core::BitSet;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
-/// SmallAsciiBitset can store letters and digits
-pub struct SmallAsciiBitset {
-    data: u64,
+/// A set of key indices. It grows in words of 64 bits, so any number of keys fits.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeySet {
+    words: Vec<u64>,
 }
 
-impl SmallAsciiBitset {
-    pub fn new() -> Self {
-        Self { data: 0 }
-    }
-
-    pub fn from(c: char) -> Self {
-        let mut result = Self { data: 0 };
-        result.insert(c);
-        return result;
-    }
-
-    fn translate(c: char) -> usize {
-        // z is 122, a is 96, Z is 90, A is 65, '9' is 57, '0' is 48
-        let d = c as u8;
-        debug_assert!((d >= 96 && d <= 122) || (d >= 65 && d <= 90) || (d >= 48 && d <= 57));
-        match d {
-            48..=57 => (d - 48) as usize,       // digits map to 0-9
-            65..=90 => (d - 65 + 10) as usize,  // uppercase maps to 10-35
-            96..=122 => (d - 96 + 36) as usize, // lowercase maps to 36-62
-            _ => panic!("Invalid character"),
+impl KeySet {
+    /// An empty set which can hold the indices `0..capacity`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            words: vec![0; capacity.div_ceil(64)],
         }
     }
 
-    pub fn insert(&mut self, c: char) {
-        self.data.bit_set(SmallAsciiBitset::translate(c));
+    pub fn insert(&mut self, key: usize) {
+        assert!(key < self.words.bit_len(), "Key index {} out of range", key);
+        self.words.bit_set(key);
     }
 
-    pub fn contains(&self, c: char) -> bool {
-        self.data.bit_test(SmallAsciiBitset::translate(c))
+    pub fn contains(&self, key: usize) -> bool {
+        self.words.bit_test(key)
     }
 
-    pub fn len(&self) -> usize {
-        self.data.bit_count()
+    /// Whether every element of `other` is contained in `self`.
+    pub fn contains_all(&self, other: &Self) -> bool {
+        self.words.bit_superset(&other.words)
     }
+}
 
-    pub fn union(&mut self, other: &Self) {
-        self.data.bit_or(&other.data);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyset_wide_test() {
+        let mut keys = KeySet::with_capacity(130);
+        keys.insert(0);
+        keys.insert(64);
+        keys.insert(129);
+        assert!(keys.contains(64));
+        assert!(!keys.contains(1));
+        assert!(!keys.contains(128));
+
+        let mut other = KeySet::with_capacity(130);
+        other.insert(129);
+        assert!(keys.contains_all(&other));
+        other.insert(65);
+        assert!(!keys.contains_all(&other));
     }
 }
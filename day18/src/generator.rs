@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use aoc2019::graph;
+use aoc2019::grid::{self, Point};
+
+use crate::graph::door_to_key;
+use crate::grid::{Grid, KeyId, Tile, FIRST_NUMERIC_ID};
+
+/// Generates random vaults for benchmarking the solver.
+///
+/// The maze is a spanning tree of `width` x `height` rooms, so there is exactly one way between
+/// any two tiles. Keys are labelled `a`, `b`, ... and continue with other lowercase Unicode
+/// letters once the alphabet runs out, and with numeric ids once the letters run out. Every door is placed so that all keys behind it come later
+/// in that order, and no robot starts behind a door, which keeps every generated vault solvable.
+pub struct Generator {
+    pub width: usize,
+    pub height: usize,
+    pub keys: usize,
+    pub entrances: usize,
+}
+
+impl Generator {
+    pub fn generate(&self, seed: u64) -> Grid {
+        assert!(self.entrances >= 1, "A vault needs at least one entrance");
+        assert!(
+            self.keys + self.entrances <= self.width * self.height,
+            "Not enough rooms for all keys and entrances"
+        );
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut tiles = self
+            .carve(&mut rng)
+            .map(|&c| if c == '#' { Tile::Wall } else { Tile::Open });
+
+        let mut rooms: Vec<Point> = tiles.positions(|&t| t == Tile::Open);
+        rooms.retain(|p| p.x % 2 == 1 && p.y % 2 == 1);
+        rooms.shuffle(&mut rng);
+        let entrances = &rooms[..self.entrances];
+        let keys = &rooms[self.entrances..self.entrances + self.keys];
+        let ids = key_ids(self.keys);
+        for &p in entrances {
+            tiles[p] = Tile::Entrance(0);
+        }
+        for (&p, &id) in keys.iter().zip(ids.iter()) {
+            tiles[p] = Tile::Key(id);
+        }
+
+        // for every tile: the lowest key index behind it, and whether a robot starts behind it
+        let parents = graph::bfs_tree(entrances[0], |p: Point| {
+            let tiles = &tiles;
+            tiles
+                .neighbours4(p)
+                .filter(move |&n| tiles[n] != Tile::Wall)
+        });
+        let mut lowest_key: HashMap<Point, usize> = HashMap::new();
+        let mut has_entrance = HashSet::new();
+        for (i, &key) in keys.iter().enumerate() {
+            for p in graph::ancestors(&parents, key) {
+                let lowest = lowest_key.entry(p).or_insert(i);
+                *lowest = (*lowest).min(i);
+            }
+        }
+        for &entrance in entrances {
+            has_entrance.extend(graph::ancestors(&parents, entrance));
+        }
+
+        for (i, &id) in ids.iter().enumerate() {
+            let candidates: Vec<Point> = tiles
+                .positions(|&t| t == Tile::Open)
+                .into_iter()
+                .filter(|p| lowest_key.get(p).is_some_and(|&lowest| lowest > i))
+                .filter(|p| !has_entrance.contains(p))
+                .collect();
+            if let Some(&p) = candidates.choose(&mut rng) {
+                tiles[p] = Tile::Door(id);
+            }
+        }
+        Grid::from_tiles(tiles)
+    }
+
+    /// A perfect maze: rooms on odd coordinates, connected by a randomized depth-first search.
+    fn carve(&self, rng: &mut StdRng) -> grid::Grid<char> {
+        let mut tiles = grid::Grid::new(2 * self.width + 1, 2 * self.height + 1, '#');
+        let room = |x: usize, y: usize| Point::new(2 * x as i64 + 1, 2 * y as i64 + 1);
+        let start = room(rng.gen_range(0..self.width), rng.gen_range(0..self.height));
+        tiles[start] = '.';
+        let mut stack = vec![start];
+        while let Some(&current) = stack.last() {
+            let unvisited: Vec<Point> =
+                IntoIterator::into_iter([Point::NORTH, Point::SOUTH, Point::EAST, Point::WEST])
+                    .map(|d| current + d + d)
+                    .filter(|&next| {
+                        tiles.get(next) == Some(&'#') && next.x % 2 == 1 && next.y % 2 == 1
+                    })
+                    .collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    let wall = Point::new((current.x + next.x) / 2, (current.y + next.y) / 2);
+                    tiles[wall] = '.';
+                    tiles[next] = '.';
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+        tiles
+    }
+}
+
+/// The ids of `n` keys: letters as long as there are any, numeric ids after that.
+fn key_ids(n: usize) -> Vec<KeyId> {
+    let mut ids: Vec<KeyId> = labels(n).into_iter().map(|c| c as KeyId).collect();
+    let letters = ids.len();
+    ids.extend((0..(n - letters) as KeyId).map(|i| FIRST_NUMERIC_ID + i));
+    ids
+}
+
+/// The first `n` lowercase letters which have a single uppercase counterpart mapping back to them.
+fn labels(n: usize) -> Vec<char> {
+    ('a'..=char::MAX)
+        .filter(|&c| {
+            let mut upper = c.to_uppercase();
+            match (upper.next(), upper.next()) {
+                (Some(u), None) => c.is_lowercase() && u.is_uppercase() && door_to_key(u) == c,
+                _ => false,
+            }
+        })
+        .take(n)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn labels_test() {
+        let labels = labels(40);
+        assert_eq!('a', labels[0]);
+        assert_eq!('z', labels[25]);
+        assert!(labels[26..].iter().all(|c| !c.is_ascii()));
+    }
+
+    #[test]
+    fn key_ids_test() {
+        let letters = labels(usize::MAX).len();
+        let ids = key_ids(letters + 2);
+        assert_eq!('a' as KeyId, ids[0]);
+        assert_eq!(
+            vec![FIRST_NUMERIC_ID, FIRST_NUMERIC_ID + 1],
+            ids[letters..].to_vec()
+        );
+    }
+
+    #[test]
+    fn generator_test() {
+        let generator = Generator {
+            width: 12,
+            height: 8,
+            keys: 10,
+            entrances: 2,
+        };
+        let grid = generator.generate(42);
+        assert_eq!(25, grid.tiles.width());
+        assert_eq!(17, grid.tiles.height());
+        assert_eq!(2, grid.entrances.len());
+        assert_eq!(10, grid.keys.len());
+        assert!(!grid.doors.is_empty());
+
+        let route = Solver::new(&grid).solve().unwrap();
+        assert_eq!(10, route.keys().chars().count());
+    }
+
+    #[test]
+    fn generator_unicode_test() {
+        let generator = Generator {
+            width: 20,
+            height: 20,
+            keys: 30,
+            entrances: 1,
+        };
+        let grid = generator.generate(7);
+        let route = Solver::new(&grid).solve().unwrap();
+        assert_eq!(30, route.keys().chars().count());
+        assert!(!route.keys().is_ascii());
+    }
+}
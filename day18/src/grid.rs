@@ -1,37 +1,34 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
 use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum Tile {
-    Wall,
-    Open,
-    Key(char),
-    Door(char),
-    Entrance(char),
-}
+use aoc2019::graph;
+use aoc2019::grid::{self, Point};
 
-impl Tile {
-    /// Numeric encodes the Tile to a unique u8 (ASCII).
-    pub fn char(&self) -> Option<char> {
-        use Tile::*;
-        match self {
-            Key(c) => Some(*c),
-            Door(c) => Some(*c),
-            Entrance(c) => Some(*c),
-            _ => None,
-        }
-    }
+use crate::graph::door_to_key;
 
-    pub fn is_door(&self) -> bool {
-        if let Tile::Door(_) = self {
-            return true;
-        }
-        return false;
-    }
+/// Identifies a key and the doors it opens. Keys read from text are identified by the code point
+/// of their letter. Ids from `FIRST_NUMERIC_ID` on have no letter; only the generator uses them.
+pub type KeyId = u32;
+
+pub const FIRST_NUMERIC_ID: KeyId = char::MAX as KeyId + 1;
 
+/// The letter of a key, if it has one.
+pub fn key_letter(id: KeyId) -> Option<char> {
+    std::char::from_u32(id)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tile {
+    Wall,
+    Open,
+    Key(KeyId),
+    /// the id of the key which opens the door
+    Door(KeyId),
+    /// entrances are numbered in reading order
+    Entrance(usize),
 }
 
 impl fmt::Display for Tile {
@@ -39,19 +36,20 @@ impl fmt::Display for Tile {
         match self {
             Tile::Wall => write!(f, "#"),
             Tile::Open => write!(f, "."),
-            Tile::Key(c) => write!(f, "{}", c),
-            Tile::Door(c) => write!(f, "{}", c),
-            Tile::Entrance(d) => write!(f, "{}", d),
+            // keys and doors without a letter are drawn as `?`
+            Tile::Key(id) => write!(f, "{}", key_letter(*id).unwrap_or('?')),
+            Tile::Door(id) => {
+                let letter = key_letter(*id).and_then(|c| c.to_uppercase().next());
+                write!(f, "{}", letter.unwrap_or('?'))
+            }
+            Tile::Entrance(_) => write!(f, "@"),
         }
     }
 }
 
-type Point = (usize, usize);
-
 #[derive(Debug)]
 pub struct Grid {
-    // first dimension is always row, second dim is column
-    pub tiles: Vec<Vec<Tile>>,
+    pub tiles: grid::Grid<Tile>,
     pub doors: Vec<Point>,
     pub keys: Vec<Point>,
     pub entrances: Vec<Point>,
@@ -67,144 +65,73 @@ impl Grid {
     }
 
     pub fn from_string(data: &str) -> Grid {
-        let mut tiles = Vec::with_capacity(81);
-        let mut doors = Vec::new();
-        let mut keys = Vec::new();
-        let mut entrance = None;
-
-        for (row, line) in data.split('\n').enumerate() {
-            let mut v = Vec::with_capacity(81);
-            for (col, c) in line.chars().enumerate() {
-                v.push(match c {
-                    '#' => Tile::Wall,
-                    '.' => Tile::Open,
-                    '@' => {
-                        entrance.replace((row, col));
-                        Tile::Entrance('0')
-                    }
-                    'a'..='z' => {
-                        keys.push((row, col));
-                        Tile::Key(c)
-                    }
-                    'A'..='Z' => {
-                        doors.push((row, col));
-                        Tile::Door(c)
-                    }
-                    _ => panic!("Unexpected input: {}", c),
-                });
-            }
-            if !v.is_empty() {
-                tiles.push(v);
-            }
+        // blank lines around the vault would be padded with spaces
+        let tiles = grid::Grid::parse(data.trim(), |_, c| match c {
+            '#' => Tile::Wall,
+            '.' => Tile::Open,
+            '@' => Tile::Entrance(0),
+            c if c.is_lowercase() => Tile::Key(c as KeyId),
+            c if c.is_uppercase() => Tile::Door(door_to_key(c) as KeyId),
+            _ => panic!("Unexpected input: {}", c),
+        });
+        Self::from_tiles(tiles)
+    }
+
+    /// Finds the keys, doors and entrances on `tiles`, and numbers the entrances in reading order.
+    pub fn from_tiles(mut tiles: grid::Grid<Tile>) -> Grid {
+        let doors = tiles.positions(|t| matches!(t, Tile::Door(_)));
+        let keys = tiles.positions(|t| matches!(t, Tile::Key(_)));
+        let entrances = tiles.positions(|t| matches!(t, Tile::Entrance(_)));
+        for (i, &p) in entrances.iter().enumerate() {
+            tiles[p] = Tile::Entrance(i);
         }
-        return Grid {
+        Grid {
             tiles,
             doors,
             keys,
-            entrances: vec![entrance.unwrap()],
-        };
+            entrances,
+        }
     }
 
     /// Find outgoing edges from the given `start` point
     pub fn edges(&self, start: Point) -> HashMap<Point, usize> {
-        let mut result = HashMap::new();
-        // perform a small BFS
-
-        let mut visited = HashSet::new();
-        visited.insert(start);
-
-        let mut queue = VecDeque::with_capacity(32);
-        queue.push_back(start);
-
-        let mut distances = HashMap::new();
-        distances.insert(start, 0);
-
-        while let Some(p) = queue.pop_front() {
-            for neighb in self.neighbors(p) {
-                if !visited.contains(&neighb) {
-                    visited.insert(neighb);
-                    let dist = distances[&p] + 1;
-                    distances.insert(neighb, dist);
-                    if self.get(neighb) == Some(Tile::Open) {
-                        queue.push_back(neighb);
-                    } else {
-                        result.insert(neighb, dist);
-                    }
-                }
-            }
-        }
-        return result;
+        // only walk across open tiles; anything else ends the edge
+        let distances = graph::bfs(start, |p| {
+            let tiles = &self.tiles;
+            let expand = p == start || tiles[p] == Tile::Open;
+            tiles
+                .neighbours4(p)
+                .filter(move |&n| expand && tiles[n] != Tile::Wall)
+        });
+        distances
+            .into_iter()
+            .filter(|&(p, _)| p != start && self.tiles[p] != Tile::Open)
+            .collect()
     }
 
     pub fn to_many_worlds(&mut self) {
         assert_eq!(1, self.entrances.len()); // do not call this method twice!
-        let (row, col) = self.entrances[0];
-        self.tiles[row][col] = Tile::Wall;
-
-        for p in &[
-            (row + 1, col),
-            (row - 1, col),
-            (row, col + 1),
-            (row, col - 1),
-        ] {
-            assert_eq!(Tile::Open, self.tiles[p.0][p.1]);
-            self.tiles[p.0][p.1] = Tile::Wall;
-        }
+        let center = self.entrances[0];
+        self.tiles[center] = Tile::Wall;
 
-        self.entrances.clear();
-        let mut i = '0';
-        for p in &[
-            (row + 1, col + 1),
-            (row - 1, col - 1),
-            (row + 1, col - 1),
-            (row - 1, col + 1),
-        ] {
-            assert_eq!(Tile::Open, self.tiles[p.0][p.1]);
-            self.tiles[p.0][p.1] = Tile::Entrance(i);
-            self.entrances.push(*p);
-            i = ((i as u8) + 1) as char;
+        for &p in center.neighbours4().iter() {
+            assert_eq!(Tile::Open, self.tiles[p]);
+            self.tiles[p] = Tile::Wall;
         }
-    }
 
-    fn get(&self, (row_idx, col_idx): Point) -> Option<Tile> {
-        if let Some(col) = self.tiles.get(row_idx) {
-            if let Some(tile) = col.get(col_idx) {
-                return Some(*tile);
-            }
-        }
-        None
-    }
-
-    /// Get all neighbor points (N, E, S, W) which are _not_ a wall.
-    fn neighbors(&self, (row_idx, col_idx): Point) -> Vec<Point> {
-        let mut result = Vec::new();
-        let candidates = [
-            (row_idx - 1, col_idx),
-            (row_idx + 1, col_idx),
-            (row_idx, col_idx - 1),
-            (row_idx, col_idx + 1),
-        ];
-        for c in &candidates {
-            if let Some(tile) = self.get(*c) {
-                if tile != Tile::Wall {
-                    result.push(*c);
-                }
-            }
+        self.entrances.clear();
+        for (i, &(dx, dy)) in [(1, 1), (-1, -1), (-1, 1), (1, -1)].iter().enumerate() {
+            let p = center + Point::new(dx, dy);
+            assert_eq!(Tile::Open, self.tiles[p]);
+            self.tiles[p] = Tile::Entrance(i);
+            self.entrances.push(p);
         }
-        return result;
     }
 }
 
 impl fmt::Display for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Grid:\n")?;
-        for row in &self.tiles {
-            for tile in row {
-                write!(f, "{}", tile)?;
-            }
-            write!(f, "\n")?;
-        }
-        return Ok(());
+        write!(f, "Grid:\n{}", self.tiles)
     }
 }
 
@@ -215,29 +142,37 @@ mod tests {
     #[test]
     fn grid_edges_test_small() {
         let grid = Grid::from_file(&"small.txt"[..]);
-        assert_eq!(vec![(4, 8)], grid.entrances);
+        assert_eq!(vec![Point::new(8, 4)], grid.entrances);
         let actual_distances = grid.edges(grid.entrances[0]);
         assert_eq!(8, actual_distances.keys().len());
         let expected = vec![
-            (1, 6),
-            (3, 6),
-            (5, 6),
-            (7, 6),
-            (1, 10),
-            (3, 10),
-            (5, 10),
-            (7, 10),
+            Point::new(6, 1),
+            Point::new(6, 3),
+            Point::new(6, 5),
+            Point::new(6, 7),
+            Point::new(10, 1),
+            Point::new(10, 3),
+            Point::new(10, 5),
+            Point::new(10, 7),
         ];
         for p in &expected {
             assert!(actual_distances.contains_key(p));
         }
     }
 
+    #[test]
+    fn from_string_blank_lines_test() {
+        let grid = Grid::from_string("\n#####\n#@.a#\n#####\n\n");
+        assert_eq!(3, grid.tiles.height());
+        assert_eq!(vec![Point::new(1, 1)], grid.entrances);
+        assert_eq!(vec![Point::new(3, 1)], grid.keys);
+    }
+
     #[test]
     fn grid_edges_test_small4() {
         let grid = Grid::from_file(&"small4.txt"[..]);
-        assert_eq!(vec![(1, 1)], grid.entrances);
-        let key_a = (1, 16);
+        assert_eq!(vec![Point::new(1, 1)], grid.entrances);
+        let key_a = Point::new(16, 1);
         let actual_distances = grid.edges(key_a);
         assert_eq!(5, actual_distances.keys().len());
     }
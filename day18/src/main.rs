@@ -1,20 +1,79 @@
 mod bitset;
+mod generator;
 mod graph;
 mod grid;
 mod part1;
 mod part2;
+mod route;
+mod solver;
 
 use env_logger;
 use log::info;
 use std::env;
 use std::time::Instant;
 
+use generator::Generator;
 use part1::part1;
 use part2::part2;
+use solver::Solver;
+
+/// Generates and solves vaults with the seeds `0..runs`, and reports how long each step took.
+fn bench(generator: &Generator, runs: u64) {
+    let mut total = 0;
+    for seed in 0..runs {
+        let start = Instant::now();
+        let grid = generator.generate(seed);
+        let generated = start.elapsed();
+        let start = Instant::now();
+        let route = Solver::new(&grid)
+            .solve()
+            .expect("Unable to collect all keys");
+        let solved = start.elapsed();
+        total += solved.as_millis();
+        println!(
+            "Seed {}: {} steps (generated in {}ms, solved in {}ms)",
+            seed,
+            route.distance,
+            generated.as_millis(),
+            solved.as_millis()
+        );
+    }
+    println!("Solved {} vaults in {}ms", runs, total);
+}
 
 fn main() {
     env_logger::init();
 
+    // generate a random vault: generate <width> <height> <keys> <entrances> [seed]
+    // solve generated vaults: bench <width> <height> <keys> <entrances> [runs]
+    let mode = env::args().nth(1);
+    if let Some(mode @ ("generate" | "bench")) = mode.as_deref() {
+        let args: Vec<usize> = env::args()
+            .skip(2)
+            .map(|arg| arg.parse().expect("invalid number"))
+            .collect();
+        if !(4..=5).contains(&args.len()) {
+            let last = if mode == "generate" { "seed" } else { "runs" };
+            panic!(
+                "usage: day18 {} <width> <height> <keys> <entrances> [{}]",
+                mode, last
+            );
+        }
+        let generator = Generator {
+            width: args[0],
+            height: args[1],
+            keys: args[2],
+            entrances: args[3],
+        };
+        if mode == "generate" {
+            let seed = args.get(4).copied().unwrap_or(0) as u64;
+            print!("{}", generator.generate(seed).tiles);
+        } else {
+            bench(&generator, args.get(4).copied().unwrap_or(10) as u64);
+        }
+        return;
+    }
+
     let mut solve_one = true;
     let mut solve_two = true;
 
@@ -0,0 +1,145 @@
+use std::fmt;
+
+use aoc2019::graph;
+use aoc2019::grid::Point;
+
+use crate::grid::{key_letter, Grid, KeyId, Tile};
+
+/// One robot walking to the next key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leg {
+    /// index into the entrances of the grid the robot started from
+    pub robot: usize,
+    pub key: KeyId,
+    pub distance: usize,
+}
+
+/// The order in which the keys were collected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub legs: Vec<Leg>,
+    pub distance: usize,
+}
+
+impl Route {
+    /// The letters of the keys, in the order they were collected; `?` for keys without one.
+    pub fn keys(&self) -> String {
+        self.legs
+            .iter()
+            .map(|leg| key_letter(leg.key).unwrap_or('?'))
+            .collect()
+    }
+
+    /// Draws the tiles walked by the robots onto the grid, marking them with `*`.
+    ///
+    /// All doors are treated as open, since a robot only walks through a door once it has the key.
+    pub fn render(&self, grid: &Grid) -> String {
+        let mut walked = grid.tiles.map(|_| false);
+        let mut robots = grid.entrances.clone();
+        for leg in &self.legs {
+            let from = robots[leg.robot];
+            let to = grid
+                .keys
+                .iter()
+                .copied()
+                .find(|&p| grid.tiles[p] == Tile::Key(leg.key))
+                .unwrap();
+            let parents = graph::bfs_tree(from, |p: Point| {
+                grid.tiles
+                    .neighbours4(p)
+                    .filter(|&n| grid.tiles[n] != Tile::Wall)
+                    .collect::<Vec<_>>()
+            });
+            for p in graph::path_to(&parents, to) {
+                walked[p] = true;
+            }
+            robots[leg.robot] = to;
+        }
+
+        let mut overlay = grid
+            .tiles
+            .map(|tile| tile.to_string().chars().next().unwrap());
+        for (p, &w) in walked.iter() {
+            if w && grid.tiles[p] == Tile::Open {
+                overlay[p] = '*';
+            }
+        }
+        overlay.render(|&c| c)
+    }
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "keys: {}", self.keys())?;
+        for leg in &self.legs {
+            writeln!(
+                f,
+                "robot {} -> {} ({} steps)",
+                leg.robot,
+                Tile::Key(leg.key),
+                leg.distance
+            )?;
+        }
+        write!(f, "total: {} steps", self.distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::FIRST_NUMERIC_ID;
+    use crate::solver::Solver;
+
+    use super::*;
+
+    #[test]
+    fn route_small2_test() {
+        let grid = Grid::from_file("small2.txt");
+        let route = Solver::new(&grid).solve().unwrap();
+        assert_eq!("abcdef", route.keys());
+        assert_eq!(
+            vec![2, 6, 10, 24, 38, 6],
+            route.legs.iter().map(|l| l.distance).collect::<Vec<_>>()
+        );
+        assert_eq!(86, route.distance);
+        assert_eq!(
+            "########################\n\
+             #f*D*E*e*C*b*A*@*a*B*c*#\n\
+             ######################*#\n\
+             #d*********************#\n\
+             ########################\n",
+            route.render(&grid)
+        );
+    }
+
+    #[test]
+    fn route_numeric_ids_test() {
+        // the keys and doors of small2.txt, without their letters
+        let grid = Grid::from_file("small2.txt");
+        let numeric = |id: KeyId| FIRST_NUMERIC_ID + id - 'a' as KeyId;
+        let grid = Grid::from_tiles(grid.tiles.map(|&tile| match tile {
+            Tile::Key(id) => Tile::Key(numeric(id)),
+            Tile::Door(id) => Tile::Door(numeric(id)),
+            tile => tile,
+        }));
+        let route = Solver::new(&grid).solve().unwrap();
+        assert_eq!("??????", route.keys());
+        assert_eq!(
+            (0..6).map(|i| FIRST_NUMERIC_ID + i).collect::<Vec<_>>(),
+            route.legs.iter().map(|l| l.key).collect::<Vec<_>>()
+        );
+        assert_eq!(86, route.distance);
+    }
+
+    #[test]
+    fn route_part2_small_test() {
+        let mut grid = Grid::from_file("part2_small.txt");
+        grid.to_many_worlds();
+        let route = Solver::new(&grid).solve().unwrap();
+        assert_eq!("abcd", route.keys());
+        assert_eq!(
+            vec![1, 0, 2, 3],
+            route.legs.iter().map(|l| l.robot).collect::<Vec<_>>()
+        );
+        assert_eq!(8, route.distance);
+    }
+}
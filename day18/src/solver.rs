@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use log::debug;
+
+use aoc2019::graph;
+
+use crate::bitset::KeySet;
+use crate::graph::Graph;
+use crate::grid::{Grid, KeyId, Tile};
+use crate::route::{Leg, Route};
+
+/// A key which can be reached from some tile.
+#[derive(Debug, Clone)]
+struct Reach {
+    key: KeyId,
+    index: usize,
+    distance: usize,
+    /// keys for the doors on the way, and keys which are picked up on the way anyway
+    required: KeySet,
+}
+
+/// Where every robot is and which keys have been collected so far.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct State {
+    robots: Vec<Tile>,
+    keys: KeySet,
+}
+
+/// Finds the shortest way to collect every key with one robot per entrance.
+///
+/// The distances between entrances and keys (and the doors in between) are computed once;
+/// the actual search is Dijkstra's algorithm over the robot positions and the collected keys.
+pub struct Solver {
+    reach: HashMap<Tile, Vec<Reach>>,
+    entrances: Vec<Tile>,
+    key_count: usize,
+    all_keys: KeySet,
+}
+
+impl Solver {
+    pub fn new(grid: &Grid) -> Self {
+        let graph = Graph::from_grid(grid);
+        let entrances: Vec<Tile> = grid.entrances.iter().map(|&p| grid.tiles[p]).collect();
+        let keys: Vec<Tile> = grid.keys.iter().map(|&p| grid.tiles[p]).collect();
+
+        // every key gets its own bit, in the order the keys appear in the grid
+        let index: HashMap<KeyId, usize> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, tile)| match tile {
+                Tile::Key(id) => (*id, i),
+                _ => unreachable!(),
+            })
+            .collect();
+        let mut all_keys = KeySet::with_capacity(keys.len());
+        for i in 0..keys.len() {
+            all_keys.insert(i);
+        }
+
+        let mut reach = HashMap::new();
+        for &tile in entrances.iter().chain(keys.iter()) {
+            reach.insert(tile, reachable_keys(&graph, &index, tile));
+        }
+        Self {
+            reach,
+            entrances,
+            key_count: keys.len(),
+            all_keys,
+        }
+    }
+
+    /// Returns `None` if some key cannot be reached.
+    pub fn solve(&self) -> Option<Route> {
+        let start = State {
+            robots: self.entrances.clone(),
+            keys: KeySet::with_capacity(self.key_count),
+        };
+        let path = graph::shortest_path(
+            start,
+            |state| state.keys == self.all_keys,
+            |state| self.moves(&state),
+        )?;
+        debug!("collected all keys in {} steps", path.cost);
+        let legs = path
+            .nodes
+            .windows(2)
+            .map(|w| self.leg(&w[0], &w[1]))
+            .collect();
+        Some(Route {
+            legs,
+            distance: path.cost,
+        })
+    }
+
+    /// The robot which moved between two consecutive states, and how far it walked.
+    fn leg(&self, from: &State, to: &State) -> Leg {
+        let robot = (0..from.robots.len())
+            .find(|&i| from.robots[i] != to.robots[i])
+            .unwrap();
+        let key = match to.robots[robot] {
+            Tile::Key(id) => id,
+            tile => panic!("Robot ended up on {:?}", tile),
+        };
+        let distance = self.reach[&from.robots[robot]]
+            .iter()
+            .find(|reach| reach.key == key)
+            .unwrap()
+            .distance;
+        Leg {
+            robot,
+            key,
+            distance,
+        }
+    }
+
+    /// Every key one of the robots can collect next, together with the distance it walks.
+    fn moves(&self, state: &State) -> Vec<(State, usize)> {
+        let mut result = Vec::new();
+        for (i, robot) in state.robots.iter().enumerate() {
+            for reach in &self.reach[robot] {
+                if state.keys.contains(reach.index) || !state.keys.contains_all(&reach.required) {
+                    continue;
+                }
+                let mut next = state.clone();
+                next.robots[i] = Tile::Key(reach.key);
+                next.keys.insert(reach.index);
+                result.push((next, reach.distance));
+            }
+        }
+        result
+    }
+}
+
+/// Shortest distances from `from` to every other key, walking through all doors.
+///
+/// Keys behind a door without a matching key are left out, since they can never be reached.
+fn reachable_keys(graph: &Graph, index: &HashMap<KeyId, usize>, from: Tile) -> Vec<Reach> {
+    let edges = |tile: Tile| graph.neighbours(tile).iter().copied();
+    graph::dijkstra(from, edges)
+        .into_iter()
+        .filter_map(|(tile, distance)| match tile {
+            Tile::Key(key) if tile != from => {
+                let path = graph::shortest_path(from, |t| t == tile, edges)?;
+                let mut required = KeySet::with_capacity(index.len());
+                for t in &path.nodes[1..path.nodes.len() - 1] {
+                    match *t {
+                        Tile::Door(id) => required.insert(*index.get(&id)?),
+                        Tile::Key(id) => required.insert(index[&id]),
+                        _ => {}
+                    }
+                }
+                Some(Reach {
+                    key,
+                    index: index[&key],
+                    distance,
+                    required,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
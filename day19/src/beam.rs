@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+
+use log::{debug, trace};
+
+use aoc2019::grid::{Grid, Point};
+
+/// The first rows may be empty; the first pulled point is searched for linearly in the rows up
+/// to this one.
+const MAX_SEARCH_ROW: u64 = 100;
+
+/// The row whose edges determine the slopes.
+const SLOPE_ROW: u64 = 100_000;
+
+/// The pulled points of a row are `left..right`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub left: u64,
+    pub right: u64,
+}
+
+impl Span {
+    pub fn width(&self) -> u64 {
+        self.right - self.left
+    }
+
+    fn middle(&self) -> u64 {
+        (self.left + self.right - 1) / 2
+    }
+}
+
+/// A tractor beam: a cone starting at the origin, so that the pulled points of each row are
+/// contiguous and both edges move right from row to row.
+///
+/// The beam is only known through `probe`, which is assumed to be expensive. Each point is
+/// probed at most once, and the edges of a row are found by binary search instead of a scan.
+pub struct Beam<F> {
+    probe: F,
+    cache: HashMap<(u64, u64), bool>,
+    slopes: Option<(f64, f64)>,
+}
+
+impl<F> Beam<F>
+where
+    F: FnMut(u64, u64) -> bool,
+{
+    pub fn new(probe: F) -> Self {
+        Self {
+            probe,
+            cache: HashMap::new(),
+            slopes: None,
+        }
+    }
+
+    /// The number of points probed so far.
+    pub fn probes(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_pulled(&mut self, x: u64, y: u64) -> bool {
+        let probe = &mut self.probe;
+        *self.cache.entry((x, y)).or_insert_with(|| probe(x, y))
+    }
+
+    /// Narrows down the edge between a pulled and a not pulled point of a row. Returns the
+    /// pulled point next to the edge.
+    fn edge(&mut self, y: u64, mut pulled: u64, mut free: u64) -> u64 {
+        while pulled.abs_diff(free) > 1 {
+            let middle = pulled.min(free) + pulled.abs_diff(free) / 2;
+            if self.is_pulled(middle, y) {
+                pulled = middle;
+            } else {
+                free = middle;
+            }
+        }
+        pulled
+    }
+
+    /// Walks from a pulled point in doubling steps until it leaves the beam, then searches
+    /// for the edge in between.
+    fn gallop(&mut self, y: u64, mut pulled: u64, right: bool) -> u64 {
+        let mut step = 1;
+        loop {
+            let next = if right {
+                pulled + step
+            } else if pulled >= step {
+                pulled - step
+            } else if self.is_pulled(0, y) {
+                return 0;
+            } else {
+                return self.edge(y, pulled, 0);
+            };
+            if !self.is_pulled(next, y) {
+                return self.edge(y, pulled, next);
+            }
+            pulled = next;
+            step *= 2;
+        }
+    }
+
+    /// The span of row `y`, if it has any pulled point within `radius` of `guess`.
+    pub fn span_near(&mut self, y: u64, guess: u64, radius: u64) -> Option<Span> {
+        let inside = (0..=radius)
+            .flat_map(|d| [guess + d, guess.saturating_sub(d)])
+            .find(|&x| self.is_pulled(x, y))?;
+        let left = self.gallop(y, inside, false);
+        let right = self.gallop(y, inside, true) + 1;
+        trace!("row {} spans {}..{}", y, left, right);
+        Some(Span { left, right })
+    }
+
+    /// The span of row `y` which comes after a row spanning `previous`. The edges only move a
+    /// little from one row to the next, so the new span is searched for next to the old one.
+    pub fn next_span(&mut self, y: u64, previous: Span) -> Option<Span> {
+        self.span_near(y, previous.middle(), previous.width() + 1)
+    }
+
+    /// The first non-empty row after the origin, and its span.
+    fn first_span(&mut self) -> Result<(u64, Span), String> {
+        for y in 1..=MAX_SEARCH_ROW {
+            // the beam is at most 45° beyond the diagonal
+            if let Some(span) = self.span_near(y, 0, 2 * y) {
+                return Ok((y, span));
+            }
+        }
+        Err(format!("No beam in the first {} rows", MAX_SEARCH_ROW))
+    }
+
+    /// The slopes of the left and the (exclusive) right edge, i.e. `x / y` along the edges. They
+    /// are measured on a row far away, which is reached by repeatedly scaling a known span.
+    pub fn slopes(&mut self) -> Result<(f64, f64), String> {
+        if let Some(slopes) = self.slopes {
+            return Ok(slopes);
+        }
+        let (mut y, mut span) = self.first_span()?;
+        while y < SLOPE_ROW {
+            let guess = span.middle() * 10;
+            y *= 10;
+            span = self
+                .span_near(y, guess, span.width() * 10)
+                .ok_or_else(|| format!("Lost the beam in row {}", y))?;
+        }
+        let slopes = (span.left as f64 / y as f64, span.right as f64 / y as f64);
+        debug!(
+            "slopes {:?} from row {} ({} probes)",
+            slopes,
+            y,
+            self.probes()
+        );
+        self.slopes = Some(slopes);
+        Ok(slopes)
+    }
+
+    /// The span of any row, found close to where the slopes predict it.
+    pub fn span(&mut self, y: u64) -> Result<Option<Span>, String> {
+        let (left, right) = self.slopes()?;
+        let guess = ((left + right) / 2.0 * y as f64) as u64;
+        let radius = ((right - left) * y as f64) as u64 + 2;
+        Ok(self.span_near(y, guess, radius))
+    }
+
+    /// The spans of the rows `0..height`.
+    pub fn spans(&mut self, height: u64) -> Result<Vec<Option<Span>>, String> {
+        let mut spans = Vec::with_capacity(height as usize);
+        let mut previous = None;
+        for y in 0..height {
+            let span = match previous.and_then(|p| self.next_span(y, p)) {
+                Some(span) => Some(span),
+                None => self.span(y)?,
+            };
+            spans.push(span);
+            previous = span;
+        }
+        Ok(spans)
+    }
+
+    /// The number of pulled points in the `size`x`size` area at the origin.
+    pub fn count(&mut self, size: u64) -> Result<u64, String> {
+        let spans = self.spans(size)?;
+        Ok(spans
+            .iter()
+            .flatten()
+            .map(|s| s.right.min(size).saturating_sub(s.left))
+            .sum())
+    }
+
+    pub fn render(&mut self, width: u64, height: u64) -> Result<String, String> {
+        let mut pulled = Grid::new(width as usize, height as usize, false);
+        for (y, span) in self.spans(height)?.iter().enumerate() {
+            if let Some(span) = span {
+                for x in span.left..span.right.min(width) {
+                    pulled.set(Point::new(x as i64, y as i64), true);
+                }
+            }
+        }
+        Ok(pulled.render(|&p| if p { '#' } else { '.' }))
+    }
+
+    /// Whether a square of `size` fits with its bottom left corner at the left edge of row `y`.
+    /// Returns the top left corner if it does.
+    fn fits(&mut self, y: u64, size: u64) -> Result<Option<(u64, u64)>, String> {
+        if y + 1 < size {
+            return Ok(None);
+        }
+        let top = y + 1 - size;
+        match (self.span(y)?, self.span(top)?) {
+            (Some(bottom), Some(t)) if t.right >= bottom.left + size => {
+                Ok(Some((bottom.left, top)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The top left corner of the closest square of `size` which fits into the beam.
+    ///
+    /// The left edge at `a * y` and the right edge at `b * y` are `size` apart `size - 1` rows
+    /// above: `b * (y - size + 1) - a * y = size`. The real edges are rounded, so the square may
+    /// fit in a row and not in the next one, but it never fits more than `1 / (b - a)` rows
+    /// before that estimate. The search starts a bit earlier and walks south to the first row
+    /// where the square fits.
+    pub fn square(&mut self, size: u64) -> Result<(u64, u64), String> {
+        if size == 0 {
+            return Err("The square must not be empty".to_string());
+        }
+        let (a, b) = self.slopes()?;
+        let estimate = (size as f64 + b * (size - 1) as f64) / (b - a);
+        let margin = (2.0 / (b - a)) as u64 + 2;
+        let mut y = (estimate as u64).saturating_sub(margin).max(size - 1);
+        debug!(
+            "square of size {} expected at row {}",
+            size, estimate as u64
+        );
+
+        // in case the slopes are off
+        while y > size - 1 && self.fits(y, size)?.is_some() {
+            y = y.saturating_sub(margin).max(size - 1);
+        }
+        while self.fits(y, size)?.is_none() {
+            y += 1;
+        }
+        let corner = self.fits(y, size)?.unwrap();
+        debug!(
+            "square of size {} at {:?} ({} probes)",
+            size,
+            corner,
+            self.probes()
+        );
+        Ok(corner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A beam between the slopes 3/5 and 4/5, and a brute force version of it.
+    fn pulled(x: u64, y: u64) -> bool {
+        5 * x >= 3 * y && 5 * x < 4 * y
+    }
+
+    fn brute_square(size: u64) -> (u64, u64) {
+        for y in 0.. {
+            for x in 0..=y {
+                if (0..size).all(|d| pulled(x + d, y) && pulled(x, y + d)) {
+                    return (x, y);
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn test_spans() {
+        let mut beam = Beam::new(pulled);
+        let spans = beam.spans(200).unwrap();
+        for (y, span) in spans.into_iter().enumerate() {
+            let y = y as u64;
+            let expected: Vec<u64> = (0..y).filter(|&x| pulled(x, y)).collect();
+            match span {
+                Some(span) => assert_eq!(expected, (span.left..span.right).collect::<Vec<_>>()),
+                None => assert!(expected.is_empty(), "row {} is not empty", y),
+            }
+        }
+        let (left, right) = beam.slopes().unwrap();
+        assert!((left - 0.6).abs() < 1e-4 && (right - 0.8).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_count() {
+        let mut beam = Beam::new(pulled);
+        let expected = (0..50)
+            .flat_map(|y| (0..50).map(move |x| (x, y)))
+            .filter(|&(x, y)| pulled(x, y));
+        assert_eq!(expected.count() as u64, beam.count(50).unwrap());
+    }
+
+    #[test]
+    fn test_square() {
+        let mut beam = Beam::new(pulled);
+        for size in 1..=30 {
+            assert_eq!(
+                brute_square(size),
+                beam.square(size).unwrap(),
+                "size {}",
+                size
+            );
+        }
+
+        let mut beam = Beam::new(pulled);
+        let (x, y) = beam.square(1000).unwrap();
+        let left = |y: u64| (3 * y).div_ceil(5);
+        let right = |y: u64| (4 * y).div_ceil(5);
+        let fits = |top: u64| right(top) >= left(top + 999) + 1000;
+        assert_eq!(left(y + 999), x);
+        assert!(fits(y) && !fits(y - 1));
+        assert!(beam.probes() < 2000, "{} probes", beam.probes());
+    }
+
+    #[test]
+    fn test_no_beam() {
+        let mut beam = Beam::new(|_, _| false);
+        assert_eq!(
+            Err("No beam in the first 100 rows".to_string()),
+            beam.square(10)
+        );
+    }
+}
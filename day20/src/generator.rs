@@ -0,0 +1,228 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use aoc2019::grid::{self, Point};
+
+use crate::maze::Portal;
+
+/// Generates random donut mazes.
+///
+/// The donut is a ring of rooms, `thickness` rooms wide, around a hole. The rooms are connected
+/// by a random spanning tree, plus a few extra openings so there is more than one way around.
+/// Every portal has one end on the outer edge and one on the inner edge; AA and ZZ are both
+/// on the outer edge. Since the ring is connected, ZZ can always be reached without portals.
+pub struct Generator {
+    /// width of the donut in rooms
+    pub width: usize,
+    /// height of the donut in rooms
+    pub height: usize,
+    pub thickness: usize,
+    /// number of portal pairs, not counting AA and ZZ
+    pub portals: usize,
+}
+
+/// A generated maze, together with where its portals are.
+#[derive(Debug, Clone)]
+pub struct Donut {
+    pub maze: String,
+    pub start: Point,
+    pub finish: Point,
+    /// the passages next to the outer and inner end of every portal
+    pub portals: Vec<(Portal, Point, Point)>,
+}
+
+/// Where a portal can be placed: the passage on the edge, and the direction of its label.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    passage: Point,
+    outwards: Point,
+}
+
+/// Rows and columns reserved for labels around the donut.
+const MARGIN: i64 = 2;
+
+impl Generator {
+    pub fn generate(&self, seed: u64) -> Donut {
+        assert!(self.thickness >= 1, "The donut needs a ring");
+        assert!(
+            self.width >= 2 * self.thickness + 3 && self.height >= 2 * self.thickness + 3,
+            "The hole is too small for labels"
+        );
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut tiles = grid::Grid::new(
+            2 * self.width + 1 + 2 * MARGIN as usize,
+            2 * self.height + 1 + 2 * MARGIN as usize,
+            ' ',
+        );
+        self.carve(&mut tiles, &mut rng);
+
+        let mut outer = self.outer_slots();
+        let mut inner = self.inner_slots();
+        assert!(
+            self.portals + 2 <= outer.len() && self.portals <= inner.len(),
+            "Not enough room for {} portals",
+            self.portals
+        );
+        outer.shuffle(&mut rng);
+        inner.shuffle(&mut rng);
+
+        let start = place(&mut tiles, outer[0], &Portal::new('A', 'A'));
+        let finish = place(&mut tiles, outer[1], &Portal::new('Z', 'Z'));
+        let mut names = names();
+        names.shuffle(&mut rng);
+        let portals = names
+            .into_iter()
+            .zip(outer[2..].iter().zip(inner.iter()))
+            .take(self.portals)
+            .map(|(portal, (&o, &i))| {
+                let outer_passage = place(&mut tiles, o, &portal);
+                let inner_passage = place(&mut tiles, i, &portal);
+                (portal, outer_passage, inner_passage)
+            })
+            .collect();
+
+        Donut {
+            maze: tiles.render(|&c| c),
+            start,
+            finish,
+            portals,
+        }
+    }
+
+    /// The position of room (`x`, `y`) in the maze.
+    fn room(x: usize, y: usize) -> Point {
+        Point::new(2 * x as i64 + 1 + MARGIN, 2 * y as i64 + 1 + MARGIN)
+    }
+
+    fn in_ring(&self, x: usize, y: usize) -> bool {
+        let t = self.thickness;
+        x < t || y < t || x + t >= self.width || y + t >= self.height
+    }
+
+    /// Fills the donut with walls and connects all rooms of the ring.
+    fn carve(&self, tiles: &mut grid::Grid<char>, rng: &mut StdRng) {
+        let top_left = Point::new(MARGIN, MARGIN);
+        let bottom_right = Self::room(self.width - 1, self.height - 1) + Point::new(1, 1);
+        let t = self.thickness;
+        let hole = (
+            Self::room(t, t),
+            Self::room(self.width - t - 1, self.height - t - 1),
+        );
+        for p in tiles.points() {
+            let in_donut = p.x >= top_left.x
+                && p.y >= top_left.y
+                && p.x <= bottom_right.x
+                && p.y <= bottom_right.y;
+            let in_hole = p.x >= hole.0.x && p.y >= hole.0.y && p.x <= hole.1.x && p.y <= hole.1.y;
+            if in_donut && !in_hole {
+                tiles[p] = '#';
+            }
+        }
+
+        let rooms: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.in_ring(x, y))
+            .collect();
+        let is_room = |p: Point, tiles: &grid::Grid<char>| {
+            rooms.iter().any(|&(x, y)| Self::room(x, y) == p) && tiles[p] == '#'
+        };
+
+        let start = Self::room(0, 0);
+        tiles[start] = '.';
+        let mut stack = vec![start];
+        while let Some(&current) = stack.last() {
+            let unvisited: Vec<Point> =
+                IntoIterator::into_iter([Point::NORTH, Point::SOUTH, Point::EAST, Point::WEST])
+                    .map(|d| current + d + d)
+                    .filter(|&next| is_room(next, tiles))
+                    .collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    let wall = Point::new((current.x + next.x) / 2, (current.y + next.y) / 2);
+                    tiles[wall] = '.';
+                    tiles[next] = '.';
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+
+        // knock out some walls between rooms, so there are loops
+        for &(x, y) in &rooms {
+            for &d in &[Point::EAST, Point::SOUTH] {
+                let wall = Self::room(x, y) + d;
+                let behind = wall + d;
+                if tiles.get(behind) == Some(&'.') && rng.gen_range(0..8) == 0 {
+                    tiles[wall] = '.';
+                }
+            }
+        }
+    }
+
+    fn outer_slots(&self) -> Vec<Slot> {
+        let mut slots = Vec::new();
+        for x in 0..self.width {
+            slots.push(Slot::new(Self::room(x, 0), Point::NORTH));
+            slots.push(Slot::new(Self::room(x, self.height - 1), Point::SOUTH));
+        }
+        for y in 0..self.height {
+            slots.push(Slot::new(Self::room(0, y), Point::WEST));
+            slots.push(Slot::new(Self::room(self.width - 1, y), Point::EAST));
+        }
+        slots
+    }
+
+    /// Slots on the edge of the hole. The corners are left out, since labels from two sides
+    /// would touch there.
+    fn inner_slots(&self) -> Vec<Slot> {
+        let t = self.thickness;
+        let mut slots = Vec::new();
+        for x in t + 1..self.width - t - 1 {
+            slots.push(Slot::new(Self::room(x, t - 1), Point::SOUTH));
+            slots.push(Slot::new(Self::room(x, self.height - t), Point::NORTH));
+        }
+        for y in t + 1..self.height - t - 1 {
+            slots.push(Slot::new(Self::room(t - 1, y), Point::EAST));
+            slots.push(Slot::new(Self::room(self.width - t, y), Point::WEST));
+        }
+        slots
+    }
+}
+
+impl Slot {
+    /// The slot next to `room`, on its side facing `outwards`.
+    fn new(room: Point, outwards: Point) -> Self {
+        Self {
+            passage: room + outwards,
+            outwards,
+        }
+    }
+}
+
+/// Opens the passage of `slot` and writes the label of `portal` next to it, to be read
+/// left-to-right or top-to-bottom. Returns the passage.
+fn place(tiles: &mut grid::Grid<char>, slot: Slot, portal: &Portal) -> Point {
+    tiles[slot.passage] = '.';
+    let near = slot.passage + slot.outwards;
+    let far = near + slot.outwards;
+    let (first, second) = if slot.outwards == Point::NORTH || slot.outwards == Point::WEST {
+        (far, near)
+    } else {
+        (near, far)
+    };
+    tiles[first] = portal.first;
+    tiles[second] = portal.second;
+    slot.passage
+}
+
+/// Portal names. The first letter always comes before the second, so no name is the reverse of
+/// another one (or AA and ZZ).
+fn names() -> Vec<Portal> {
+    ('A'..='Z')
+        .flat_map(|first| ('A'..='Z').map(move |second| Portal::new(first, second)))
+        .filter(|portal| portal.first < portal.second)
+        .collect()
+}
@@ -1,4 +1,6 @@
+mod generator;
 mod maze;
+mod portal_graph;
 
 use std::env;
 use std::time::Instant;
@@ -6,40 +8,63 @@ use std::time::Instant;
 use env_logger;
 use log::{debug, info};
 
-use crate::maze::{read_input, Point, Portal};
+use crate::generator::Generator;
+use crate::maze::{read_input, Portal};
+
+/// How deep part 2 may descend into the recursive maze.
+const MAX_DEPTH: usize = 1000;
 
 fn part1(fname: &str) -> usize {
     let grid = read_input(fname);
     let start = grid.find_portals(&Portal::new('A', 'A'))[0];
     debug!("start at: {:?}", start);
 
-    let parents = grid.bfs(start);
-
     let finish = grid.find_portals(&Portal::new('Z', 'Z'))[0];
-    debug!("finish at: {:?}", start);
-
-    // build path
-    let mut path: Vec<Point> = Vec::new();
-    let mut current = finish;
-    path.push(current);
-    while let Some(parent) = parents.get(&current) {
-        path.push(*parent);
-        current = *parent;
-    }
+    debug!("finish at: {:?}", finish);
 
-    let answer = path.len() - 1;
-    return answer;
+    return grid.distances(start)[&finish];
 }
 
 fn part2(fname: &str) -> usize {
     let grid = read_input(fname);
     let start = grid.find_portals(&Portal::new('A', 'A'))[0];
-    return grid.recursive_walk(start);
+    let walk = grid
+        .recursive_walk(start, MAX_DEPTH)
+        .expect("Unable to reach ZZ");
+    for comp in &walk.portals {
+        debug!("{}", comp);
+    }
+    return walk.steps;
 }
 
 fn main() {
     env_logger::init();
 
+    // generate a random donut: generate <width> <height> <thickness> <portals> [seed]
+    if env::args().nth(1).as_deref() == Some("generate") {
+        let args: Vec<usize> = env::args()
+            .skip(2)
+            .map(|arg| arg.parse().expect("invalid number"))
+            .collect();
+        let generator = Generator {
+            width: args[0],
+            height: args[1],
+            thickness: args[2],
+            portals: args[3],
+        };
+        let seed = args.get(4).copied().unwrap_or(0) as u64;
+        let donut = generator.generate(seed);
+        info!("AA at {}, ZZ at {}", donut.start, donut.finish);
+        for (portal, outer, inner) in &donut.portals {
+            info!(
+                "{} connects {} (outer) with {} (inner)",
+                portal, outer, inner
+            );
+        }
+        print!("{}", donut.maze);
+        return;
+    }
+
     let mut solve_one = true;
     let mut solve_two = true;
 
@@ -2,10 +2,15 @@ use std::fs::File;
 use std::io::prelude::*;
 use std::io::BufReader;
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
 use std::fmt;
 
-use log::{debug, info, trace};
+use log::{debug, trace};
+
+use aoc2019::graph;
+use aoc2019::grid::{self, Point};
+
+use crate::portal_graph::PortalGraph;
 
 const WALL: char = '#';
 const PASSAGE: char = '.';
@@ -36,110 +41,51 @@ impl fmt::Display for Portal {
 }
 
 pub struct Grid {
-    // first dimension is row, second dimension is col
-    inner: Vec<Vec<char>>,
-    _col_count: usize,
-}
-
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub struct Point {
-    pub row: usize,
-    pub col: usize,
+    inner: grid::Grid<char>,
 }
 
 impl Grid {
     pub fn row_count(&self) -> usize {
-        self.inner.len()
+        self.inner.height()
     }
 
     pub fn col_count(&self) -> usize {
-        self._col_count
+        self.inner.width()
     }
 
     pub fn from_string(s: &str) -> Self {
-        let mut inner: Vec<Vec<char>> = Vec::new();
-        let mut max_col_count = 0;
-        for line in s.lines() {
-            let mut row = Vec::new();
-            for c in line.trim_end().chars() {
-                row.push(c);
-            }
-            let n = row.len();
-            if n > max_col_count {
-                max_col_count = n;
-            }
-            inner.push(row);
-        }
+        let lines: Vec<&str> = s.lines().map(|line| line.trim_end()).collect();
         Self {
-            inner,
-            _col_count: max_col_count,
+            inner: grid::Grid::parse(&lines.join("\n"), |_, c| c),
         }
     }
 
     /// Returns the entrance point for the given portal.
     /// The returned point is chosen such that it is located on a PASSAGE.
     pub fn find_portals(&self, portal: &Portal) -> Vec<Point> {
+        let is = |p: Point, c: char| self.inner.get(p) == Some(&c);
         let mut portals = Vec::with_capacity(2);
         // labels can oriented horizontally (left-to-right) or vertically (top-to-bottom)
-        for (row_num, row) in self.inner.iter().enumerate() {
-            for (col, &c) in row.iter().enumerate() {
-                if c == portal.first {
-                    if let Some(true) = row.get(col + 1).map(move |val| *val == portal.second) {
-                        // horizontal
-                        let mut found = false;
-                        if let Some(&c) = self.inner[row_num].get(col + 2) {
-                            if c == PASSAGE {
-                                // passage is east
-                                portals.push(Point {
-                                    row: row_num,
-                                    col: col + 2,
-                                });
-                                found = true;
-                            }
-                        }
-                        if !found {
-                            // passage is west
-                            portals.push(Point {
-                                row: row_num,
-                                col: col - 1,
-                            });
-                        }
-                    } else if let Some(next_row) = self.inner.get(row_num + 1) {
-                        if let Some(true) = next_row.get(col).map(move |val| *val == portal.second)
-                        {
-                            // vertical
-                            let mut found = false;
-                            if let Some(next_next_row) = self.inner.get(row_num + 2) {
-                                if let Some(&c) = next_next_row.get(col) {
-                                    if c == PASSAGE {
-                                        // passage is south
-                                        portals.push(Point {
-                                            row: row_num + 2,
-                                            col,
-                                        });
-                                        found = true;
-                                    }
-                                }
-                            }
-                            if !found {
-                                // passage is north
-                                portals.push(Point {
-                                    row: row_num - 1,
-                                    col,
-                                });
-                            }
-                        }
-                    }
+        for p in self.inner.positions(|&c| c == portal.first) {
+            for &direction in &[Point::EAST, Point::SOUTH] {
+                if is(p + direction, portal.second) {
+                    // the passage is either behind the second letter or before the first one
+                    let behind = p + direction + direction;
+                    portals.push(if is(behind, PASSAGE) {
+                        behind
+                    } else {
+                        p - direction
+                    });
                 }
             }
         }
-        return portals;
+        portals
     }
 
     /// Determine whether `portal` is outer or inner.
     fn is_outer_portal(&self, point: &Point) -> bool {
-        let row_idx = point.row;
-        let col_idx = point.col;
+        let row_idx = point.y as usize;
+        let col_idx = point.x as usize;
         // first point of portal is on PASSAGE, hence all numbers are +1
         let is_outer = row_idx <= 2 // first two rows are 'outer'
             || row_idx + 3 >= self.row_count() // last two rows are 'outer'
@@ -150,43 +96,22 @@ impl Grid {
     }
 
     fn neighbors(&self, current: Point) -> Vec<Point> {
-        let mut result: Vec<Point> = Vec::new();
-        let nums: [(i64, i64); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
-        for (drow, dcol) in &nums {
-            let row_idx = (current.row as i64) + *drow;
-            if row_idx < 0 {
-                continue;
-            }
-            let row_idx = row_idx as usize;
-            if let Some(col) = self.inner.get(row_idx) {
-                let col_idx = (current.col as i64) + *dcol;
-                if col_idx < 0 {
-                    continue;
-                }
-                let col_idx = col_idx as usize;
-                if let Some(&c) = col.get(col_idx) {
-                    if c != WALL {
-                        result.push(Point {
-                            row: row_idx,
-                            col: col_idx,
-                        });
-                    }
-                }
-            }
-        }
-        return result;
+        self.inner
+            .neighbours4(current)
+            .filter(|&p| self.inner[p] != WALL)
+            .collect()
     }
 
     // `point` is the first char of a portal. returns the two letters of the portal,
     // i.e. it discovers the second char.
     fn discover_portal(&self, point: Point) -> Option<Portal> {
         trace!("discovering portal at {:?}", point);
-        let c = self.inner[point.row][point.col];
+        let c = self.inner[point];
         if !c.is_ascii() || !c.is_uppercase() {
             return None;
         }
         for np in self.neighbors(point) {
-            let d = self.inner[np.row][np.col];
+            let d = self.inner[np];
             if d.is_ascii() && d.is_uppercase() {
                 return Some(Portal::new(c, d));
             }
@@ -194,213 +119,110 @@ impl Grid {
         return None;
     }
 
-    /// Find all portals reachable from `start` and return their distance.
-    pub fn bfs(&self, start: Point) -> HashMap<Point, Point> {
-        // bfs
-        let mut queue: VecDeque<(Point, usize)> = VecDeque::new();
-        let mut discovered: HashSet<Point> = HashSet::new();
-
-        // value: parent Point and distance
-        let mut parents: HashMap<Point, Point> = HashMap::new();
-        queue.push_back((start, 0));
-        discovered.insert(start);
-
-        while let Some((current, dist)) = queue.pop_front() {
-            debug!("visiting {:?}, dist {}", current, dist);
-            for point in self.neighbors(current) {
-                if !discovered.contains(&point) {
-                    discovered.insert(point);
-                    parents.insert(point, current);
-
-                    let c = self.inner[point.row][point.col];
-                    debug!("discovered {:?}: {}", point, c);
-
-                    if c == PASSAGE {
-                        queue.push_back((point, dist + 1));
-                        continue;
-                    }
-                    // portal, but not the portal where we started from
-                    // add the point **before** it to the result
-                    // now go through portal
-                    let portal = self.discover_portal(point).unwrap();
-                    debug!("found portal {}", portal);
-
-                    let mut portal_points = self.find_portals(&portal);
-                    // portal string might be reversed
-                    if portal_points.is_empty() {
-                        portal_points = self.find_portals(&portal.reverse());
-                    }
-                    if let Some(other_portal_point) = portal_points.iter().find(|&&p| p != current)
-                    {
-                        // go through portal
-                        info!(
-                            "portal {} at {:?} is connected with point {:?}. adding to queue.",
-                            portal, current, other_portal_point
-                        );
-                        if !discovered.contains(other_portal_point) {
-                            discovered.insert(*other_portal_point);
-                            parents.insert(*other_portal_point, current);
-                            queue.push_back((*other_portal_point, dist + 1));
-                        }
-                    }
-                }
-            }
+    /// The portal whose label contains the letter at `letter`, named the way it is written.
+    fn portal_at(&self, letter: Point) -> Option<Portal> {
+        let portal = self.discover_portal(letter)?;
+        // portal string might be reversed
+        if self.find_portals(&portal).is_empty() {
+            Some(portal.reverse())
+        } else {
+            Some(portal)
         }
-        return parents;
     }
 
-    pub fn recursive_walk(&self, start: Point) -> usize {
-        // bfs
-        let mut queue: VecDeque<IntermediateResult> = VecDeque::new();
-        let mut discovered: HashSet<Discovered> = HashSet::new();
-
-        queue.push_back(IntermediateResult {
-            current: start,
-            distance: 0,
-            level: 0,
-        });
-        discovered.insert(Discovered {
-            point: start,
-            level: 0,
-        });
-
-        let mut best_dist = usize::MAX;
-        while let Some(item) = queue.pop_front() {
-            debug!("popped from queue: {}", item);
-            if item.distance > best_dist {
-                trace!("cutting off branch");
-                continue;
-            }
+    /// Every passage next to a portal label, including AA and ZZ.
+    pub(crate) fn portal_ends(&self) -> Vec<(Point, Portal)> {
+        self.inner
+            .positions(|&c| c == PASSAGE)
+            .into_iter()
+            .filter_map(|p| {
+                let portal = self
+                    .neighbors(p)
+                    .into_iter()
+                    .find_map(|letter| self.portal_at(letter))?;
+                Some((p, portal))
+            })
+            .collect()
+    }
 
-            for point in self.neighbors(item.current) {
-                let my_discover = Discovered {
-                    point,
-                    level: item.level,
-                };
-                if discovered.contains(&my_discover) {
-                    trace!("{:?} seen before", my_discover);
-                    continue;
-                }
+    /// Where stepping onto the portal letter at `letter` (coming from `from`) takes you.
+    fn teleport(&self, letter: Point, from: Point) -> Option<Point> {
+        let portal = self.portal_at(letter)?;
+        // AA and ZZ only have one end
+        self.find_portals(&portal).into_iter().find(|&p| p != from)
+    }
 
-                let c = self.inner[point.row][point.col];
-                if c == PASSAGE {
-                    trace!("neighbor is a new passage");
-                    queue.push_back(IntermediateResult {
-                        current: my_discover.point,
-                        level: my_discover.level,
-                        distance: item.distance + 1,
-                    });
-                    discovered.insert(my_discover);
-                    continue;
+    /// All steps which can be taken from the passage at `current`.
+    pub(crate) fn steps(&self, current: Point) -> Vec<Step> {
+        self.neighbors(current)
+            .into_iter()
+            .filter_map(|p| {
+                if self.inner[p] == PASSAGE {
+                    return Some(Step::Walk(p));
                 }
-                if item.distance <= 1 {
-                    // we just started, so this is AA
-                    continue;
-                }
-
-                let portal = self.discover_portal(point).unwrap();
-                if portal == Portal::new('Z', 'Z') && item.level == 0 {
-                    let d = item.distance;
-                    debug!("reached ZZ after {} steps", d);
-                    if item.distance < best_dist {
-                        info!("new best distance: {}", d);
-                        best_dist = d;
-                    }
-                    continue;
-                }
-
-                let is_outer = self.is_outer_portal(&point);
-
-                // we have found a portal, but portal might be closed:
-                // when at the outermost level, only the outer labels AA and ZZ function;
-                if is_outer {
-                    if item.level == 0
-                        && portal != Portal::new('A', 'A')
-                        && portal != Portal::new('Z', 'Z')
-                    {
-                        debug!("skipping portal because we are at the outermost level");
-                        continue;
-                    }
-                    // at any other level, AA and ZZ count as walls, but the other outer labeled tiles bring you one level outward
-                    if item.level > 0
-                        && (portal == Portal::new('A', 'A') || portal == Portal::new('Z', 'Z'))
-                    {
-                        debug!("skipping portal because we are NOT at the outermost level");
-                        continue;
-                    }
+                let to = self.teleport(p, current)?;
+                trace!("portal at {} connects {} with {}", p, current, to);
+                if self.is_outer_portal(&p) {
+                    Some(Step::Outer(to))
+                } else {
+                    Some(Step::Inner(to))
                 }
+            })
+            .collect()
+    }
 
-                debug!(
-                    "walked to {} at {:?}, level {}, is_outer: {}",
-                    portal, item.current, item.level, is_outer
-                );
+    /// Distances of all passages reachable from `start`, using every portal as a shortcut.
+    pub fn distances(&self, start: Point) -> HashMap<Point, usize> {
+        graph::bfs(start, |p| self.steps(p).into_iter().map(|step| step.to()))
+    }
 
-                let mut portal_points = self.find_portals(&portal);
-                // portal string might be reversed
-                if portal_points.is_empty() {
-                    portal_points = self.find_portals(&portal.reverse());
-                }
-                if let Some(other_portal_point) = portal_points.iter().find(|&&p| p != item.current)
-                {
-                    // go through portal
-                    let new_level = match is_outer {
-                        // if we've reached outer portal => level - 1
-                        true => item.level - 1,
-                        // if we've reached inner portal => level + 1
-                        false => item.level + 1,
-                    };
-
-                    let disc = Discovered {
-                        point: *other_portal_point,
-                        level: new_level,
-                    };
-                    if !discovered.contains(&disc) {
-                        info!(
-                            "going through portal {}: {} -> {}",
-                            &portal, item.current, other_portal_point
-                        );
-                        queue.push_back(IntermediateResult {
-                            current: disc.point,
-                            level: disc.level,
-                            distance: item.distance + 1,
-                        });
-                        discovered.insert(disc);
-                    }
-                }
-            }
+    /// The shortest walk from `start` to ZZ if the maze is recursive: inner portals lead one
+    /// level down, outer portals one level up. At the outermost level, only AA and ZZ are
+    /// open; at any other level, they are walls. Levels deeper than `max_depth` are not
+    /// explored, so this returns `None` if ZZ cannot be reached within them.
+    pub fn recursive_walk(&self, start: Point, max_depth: usize) -> Option<Walk> {
+        let finish = self.find_portals(&Portal::new('Z', 'Z'))[0];
+        let walk = PortalGraph::new(self).recursive_walk(start, finish, max_depth);
+        if let Some(walk) = &walk {
+            debug!("reached ZZ after {} steps", walk.steps);
         }
-        return best_dist;
+        walk
     }
 }
 
-#[derive(Debug)]
-struct IntermediateResult {
-    current: Point,
-    distance: usize,
-    level: usize,
+/// A walk through a recursive maze.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    pub steps: usize,
+    /// every portal taken on the way, in order
+    pub portals: Vec<PathComp>,
 }
 
-impl fmt::Display for IntermediateResult {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "current: {}, dist: {}, level: {}",
-            self.current, self.distance, self.level,
-        )
-    }
+/// A single step through the maze.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Step {
+    Walk(Point),
+    /// through an inner portal
+    Inner(Point),
+    /// through an outer portal
+    Outer(Point),
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Discovered {
-    point: Point,
-    level: usize,
+impl Step {
+    fn to(self) -> Point {
+        match self {
+            Step::Walk(p) | Step::Inner(p) | Step::Outer(p) => p,
+        }
+    }
 }
 
+/// A portal taken during a recursive walk.
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct PathComp {
-    portal: Portal,
-    level: usize,
-    outer: bool,
+pub struct PathComp {
+    pub portal: Portal,
+    /// the level the portal was entered on
+    pub level: usize,
+    pub outer: bool,
 }
 
 impl fmt::Display for PathComp {
@@ -415,12 +237,6 @@ impl fmt::Display for PathComp {
     }
 }
 
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {})", self.row, self.col)
-    }
-}
-
 pub fn read_input(fname: &str) -> Grid {
     let file = File::open(fname).unwrap();
     let mut buf_reader = BufReader::new(file);
@@ -431,13 +247,137 @@ pub fn read_input(fname: &str) -> Grid {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
+    use proptest::prelude::*;
+
     use super::*;
+    use crate::generator::{Donut, Generator};
 
     #[test]
     fn is_outer_portal_test() {
         let grid = read_input("small3.txt");
         assert_eq!(37, grid.row_count());
         assert_eq!(45, grid.col_count());
-        assert_eq!(false, grid.is_outer_portal(&Point { row: 9, col: 31 }));
+        assert_eq!(false, grid.is_outer_portal(&Point::new(31, 9)));
+    }
+
+    #[test]
+    fn recursive_walk_small3_test() {
+        let grid = read_input("small3.txt");
+        let start = grid.find_portals(&Portal::new('A', 'A'))[0];
+        let walk = grid.recursive_walk(start, 10).unwrap();
+        assert_eq!(396, walk.steps);
+        assert_eq!(
+            PathComp {
+                portal: Portal::new('X', 'F'),
+                level: 0,
+                outer: false
+            },
+            walk.portals[0]
+        );
+        assert_eq!(
+            10,
+            walk.portals.iter().map(|comp| comp.level).max().unwrap()
+        );
+        // ZZ is on level 0, so every portal going down is matched by one going up
+        assert_eq!(32, walk.portals.len());
+
+        assert_eq!(None, grid.recursive_walk(start, 9));
+    }
+
+    /// Distances by repeatedly relaxing every passage, with the portals taken from the generator.
+    fn brute_force_distances(donut: &Donut) -> HashMap<Point, usize> {
+        let tiles = grid::Grid::parse(&donut.maze, |_, c| c);
+        let mut jumps = HashMap::new();
+        for &(_, outer, inner) in &donut.portals {
+            jumps.insert(outer, inner);
+            jumps.insert(inner, outer);
+        }
+        let passages = tiles.positions(|&c| c == PASSAGE);
+        let mut distances = HashMap::new();
+        distances.insert(donut.start, 0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &p in &passages {
+                let d = match distances.get(&p) {
+                    Some(&d) => d + 1,
+                    None => continue,
+                };
+                let walk = tiles.neighbours4(p).filter(|&n| tiles[n] == PASSAGE);
+                for n in walk.chain(jumps.get(&p).copied()) {
+                    if distances.get(&n).is_none_or(|&old| d < old) {
+                        distances.insert(n, d);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    fn donuts() -> impl Strategy<Value = Donut> {
+        (1usize..=2, 0usize..=4, 0usize..=2, 0usize..=2, any::<u64>()).prop_map(
+            |(thickness, portals, extra_width, extra_height, seed)| {
+                let generator = Generator {
+                    width: 2 * thickness + 3 + extra_width,
+                    height: 2 * thickness + 3 + extra_height,
+                    thickness,
+                    portals,
+                };
+                generator.generate(seed)
+            },
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn generated_portals_test(donut in donuts()) {
+            let grid = Grid::from_string(&donut.maze);
+            prop_assert_eq!(vec![donut.start], grid.find_portals(&Portal::new('A', 'A')));
+            prop_assert_eq!(vec![donut.finish], grid.find_portals(&Portal::new('Z', 'Z')));
+            for (portal, outer, inner) in &donut.portals {
+                let ends: HashSet<Point> = grid.find_portals(portal).into_iter().collect();
+                prop_assert_eq!(ends, vec![*outer, *inner].into_iter().collect::<HashSet<_>>());
+                let outer_steps: Vec<Point> = grid
+                    .steps(*outer)
+                    .into_iter()
+                    .filter_map(|step| match step {
+                        Step::Outer(to) => Some(to),
+                        _ => None,
+                    })
+                    .collect();
+                prop_assert_eq!(vec![*inner], outer_steps);
+                let inner_steps: Vec<Point> = grid
+                    .steps(*inner)
+                    .into_iter()
+                    .filter_map(|step| match step {
+                        Step::Inner(to) => Some(to),
+                        _ => None,
+                    })
+                    .collect();
+                prop_assert_eq!(vec![*outer], inner_steps);
+            }
+        }
+
+        #[test]
+        fn distances_brute_force_test(donut in donuts()) {
+            let grid = Grid::from_string(&donut.maze);
+            prop_assert_eq!(brute_force_distances(&donut), grid.distances(donut.start));
+        }
+
+        #[test]
+        fn recursive_walk_not_shorter_test(donut in donuts()) {
+            let grid = Grid::from_string(&donut.maze);
+            let flat = grid.distances(donut.start)[&donut.finish];
+            let walk = grid.recursive_walk(donut.start, donut.portals.len()).unwrap();
+            prop_assert!(walk.steps >= flat);
+            // every level which is entered is left again
+            let down = walk.portals.iter().filter(|comp| !comp.outer).count();
+            prop_assert_eq!(down, walk.portals.len() - down);
+        }
     }
 }
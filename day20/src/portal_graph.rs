@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use log::debug;
+
+use aoc2019::graph;
+use aoc2019::grid::Point;
+
+use crate::maze::{Grid, PathComp, Portal, Step, Walk};
+
+/// Where a portal takes you.
+#[derive(Debug, Clone)]
+struct Jump {
+    to: Point,
+    portal: Portal,
+    outer: bool,
+}
+
+/// The maze compressed to the passages next to portals: the walking distances between them,
+/// and the jumps through the portals.
+pub struct PortalGraph {
+    walks: HashMap<Point, Vec<(Point, usize)>>,
+    jumps: HashMap<Point, Jump>,
+}
+
+impl PortalGraph {
+    pub fn new(grid: &Grid) -> Self {
+        let ends = grid.portal_ends();
+        let mut walks = HashMap::new();
+        let mut jumps = HashMap::new();
+        for (end, portal) in &ends {
+            let distances = graph::bfs(*end, |p| {
+                grid.steps(p).into_iter().filter_map(|step| match step {
+                    Step::Walk(to) => Some(to),
+                    _ => None,
+                })
+            });
+            let reachable = ends
+                .iter()
+                .filter_map(|(other, _)| match distances.get(other) {
+                    Some(&d) if other != end => Some((*other, d)),
+                    _ => None,
+                })
+                .collect();
+            walks.insert(*end, reachable);
+
+            for step in grid.steps(*end) {
+                let (to, outer) = match step {
+                    Step::Walk(_) => continue,
+                    Step::Inner(to) => (to, false),
+                    Step::Outer(to) => (to, true),
+                };
+                let portal = portal.clone();
+                jumps.insert(*end, Jump { to, portal, outer });
+            }
+        }
+        debug!("compressed maze to {} portal ends", walks.len());
+        Self { walks, jumps }
+    }
+
+    /// Dijkstra's algorithm over (portal end, level), see `Grid::recursive_walk`.
+    pub fn recursive_walk(&self, start: Point, finish: Point, max_depth: usize) -> Option<Walk> {
+        let path = graph::shortest_path(
+            (start, 0),
+            |state| state == (finish, 0),
+            |(p, level): (Point, usize)| {
+                let walks = self.walks[&p].iter().map(move |&(to, d)| ((to, level), d));
+                let jump = self.jumps.get(&p).and_then(|jump| match jump.outer {
+                    true if level > 0 => Some(((jump.to, level - 1), 1)),
+                    false if level < max_depth => Some(((jump.to, level + 1), 1)),
+                    _ => None,
+                });
+                walks.chain(jump)
+            },
+        )?;
+
+        // walking never changes the level, so every change of level is a jump
+        let portals = path
+            .nodes
+            .windows(2)
+            .filter(|w| w[0].1 != w[1].1)
+            .map(|w| {
+                let jump = &self.jumps[&w[0].0];
+                PathComp {
+                    portal: jump.portal.clone(),
+                    level: w[0].1,
+                    outer: jump.outer,
+                }
+            })
+            .collect();
+        Some(Walk {
+            steps: path.cost,
+            portals,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::read_input;
+
+    /// On a single level, the compressed maze has to give the same distances as the full one.
+    #[test]
+    fn flat_distances_test() {
+        for &(fname, expected) in &[("small.txt", 23), ("small2.txt", 58), ("input.txt", 674)] {
+            let grid = read_input(fname);
+            let portals = PortalGraph::new(&grid);
+            let start = grid.find_portals(&Portal::new('A', 'A'))[0];
+            let finish = grid.find_portals(&Portal::new('Z', 'Z'))[0];
+            let compressed = graph::dijkstra(start, |p| {
+                let walks = portals.walks[&p].iter().copied();
+                walks.chain(portals.jumps.get(&p).map(|jump| (jump.to, 1)))
+            });
+            let full = grid.distances(start);
+            for (p, d) in &compressed {
+                assert_eq!(full[p], *d, "{} in {}", p, fname);
+            }
+            assert_eq!(expected, compressed[&finish], "{}", fname);
+        }
+    }
+}
@@ -0,0 +1,409 @@
+//! Graph search for any node type.
+//!
+//! The search functions don't need a `Graph` at all: they take the start node and a closure
+//! returning the neighbours of a node, which makes them usable for implicit graphs (grids,
+//! mazes with portals, game states, ...). `Graph` is a plain adjacency list on top of them.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// A path found by one of the search functions, including both end points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N> {
+    pub cost: usize,
+    pub nodes: Vec<N>,
+}
+
+/// Breadth-first search from `start`; `neighbours` returns the nodes reachable in one step.
+/// Returns the distance of every reachable node (including `start` itself).
+pub fn bfs<N, F, I>(start: N, mut neighbours: F) -> HashMap<N, usize>
+where
+    N: Clone + Eq + Hash,
+    F: FnMut(N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut distances = HashMap::new();
+    distances.insert(start.clone(), 0);
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(current) = queue.pop_front() {
+        let dist = distances[&current] + 1;
+        for next in neighbours(current) {
+            if let Entry::Vacant(e) = distances.entry(next.clone()) {
+                e.insert(dist);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// Like `bfs`, but returns the parent of every reachable node instead of its distance, i.e.
+/// a shortest path tree. Use `path_to` to read a path from it.
+pub fn bfs_tree<N, F, I>(start: N, mut neighbours: F) -> HashMap<N, N>
+where
+    N: Clone + Eq + Hash,
+    F: FnMut(N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut parents = HashMap::new();
+    let mut discovered = HashSet::new();
+    discovered.insert(start.clone());
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(current) = queue.pop_front() {
+        for next in neighbours(current.clone()) {
+            if discovered.insert(next.clone()) {
+                parents.insert(next.clone(), current.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    parents
+}
+
+/// Follows the `parents` from `end` up to the root and returns the nodes from the root to `end`.
+pub fn path_to<N: Clone + Eq + Hash>(parents: &HashMap<N, N>, end: N) -> Vec<N> {
+    let mut path = ancestors(parents, end.clone());
+    path.insert(0, end);
+    path.reverse();
+    path
+}
+
+/// Dijkstra's algorithm; `neighbours` returns the nodes reachable in one step together with
+/// the cost of the step. Returns the distance of every reachable node.
+pub fn dijkstra<N, F, I>(start: N, mut neighbours: F) -> HashMap<N, usize>
+where
+    N: Clone + Ord + Hash,
+    F: FnMut(N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+{
+    let mut distances = HashMap::new();
+    distances.insert(start.clone(), 0);
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((0, start)));
+    while let Some(Reverse((dist, current))) = heap.pop() {
+        if dist > distances[&current] {
+            // outdated entry
+            continue;
+        }
+        for (next, cost) in neighbours(current) {
+            let next_dist = dist + cost;
+            if distances.get(&next).is_none_or(|&d| next_dist < d) {
+                distances.insert(next.clone(), next_dist);
+                heap.push(Reverse((next_dist, next)));
+            }
+        }
+    }
+    distances
+}
+
+/// A* search from `start` to the first node for which `is_goal` holds. `heuristic` estimates
+/// the remaining cost and must never overestimate it, otherwise the path might not be the
+/// shortest one.
+pub fn astar<N, F, I, G, H>(
+    start: N,
+    mut is_goal: G,
+    mut neighbours: F,
+    mut heuristic: H,
+) -> Option<Path<N>>
+where
+    N: Clone + Ord + Hash,
+    F: FnMut(N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    G: FnMut(N) -> bool,
+    H: FnMut(N) -> usize,
+{
+    let mut costs = HashMap::new();
+    costs.insert(start.clone(), 0);
+    let mut parents = HashMap::new();
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((heuristic(start.clone()), 0, start)));
+    while let Some(Reverse((_, cost, current))) = heap.pop() {
+        if cost > costs[&current] {
+            continue;
+        }
+        if is_goal(current.clone()) {
+            return Some(Path {
+                cost,
+                nodes: path_to(&parents, current),
+            });
+        }
+        for (next, step) in neighbours(current.clone()) {
+            let next_cost = cost + step;
+            if costs.get(&next).is_none_or(|&c| next_cost < c) {
+                costs.insert(next.clone(), next_cost);
+                parents.insert(next.clone(), current.clone());
+                heap.push(Reverse((
+                    next_cost + heuristic(next.clone()),
+                    next_cost,
+                    next,
+                )));
+            }
+        }
+    }
+    None
+}
+
+/// Shortest path from `start` to the first node for which `is_goal` holds (Dijkstra's
+/// algorithm which stops as soon as the goal is reached).
+pub fn shortest_path<N, F, I, G>(start: N, is_goal: G, neighbours: F) -> Option<Path<N>>
+where
+    N: Clone + Ord + Hash,
+    F: FnMut(N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+    G: FnMut(N) -> bool,
+{
+    astar(start, is_goal, neighbours, |_| 0)
+}
+
+/// Distances between all pairs of `nodes` (runs Dijkstra's algorithm from every node).
+/// Unreachable pairs are missing.
+pub fn all_pairs<N, F, I>(nodes: &[N], mut neighbours: F) -> HashMap<(N, N), usize>
+where
+    N: Clone + Ord + Hash,
+    F: FnMut(N) -> I,
+    I: IntoIterator<Item = (N, usize)>,
+{
+    let wanted: HashSet<N> = nodes.iter().cloned().collect();
+    let mut result = HashMap::new();
+    for from in nodes {
+        for (to, dist) in dijkstra(from.clone(), &mut neighbours) {
+            if wanted.contains(&to) {
+                result.insert((from.clone(), to), dist);
+            }
+        }
+    }
+    result
+}
+
+/// The parent, grandparent, ... of `node` in a tree given by its `parents`, up to the root.
+///
+/// If the `parents` contain a cycle, this stops before the first node which comes up again.
+pub fn ancestors<N: Clone + Eq + Hash>(parents: &HashMap<N, N>, node: N) -> Vec<N> {
+    let mut seen = HashSet::new();
+    seen.insert(node.clone());
+    let mut result = Vec::new();
+    let mut current = node;
+    while let Some(parent) = parents.get(&current) {
+        if !seen.insert(parent.clone()) {
+            break;
+        }
+        result.push(parent.clone());
+        current = parent.clone();
+    }
+    result
+}
+
+/// The deepest node which is an ancestor of both `a` and `b` (a node counts as its own
+/// ancestor here). Returns `None` if they are in different trees.
+pub fn lowest_common_ancestor<N: Clone + Eq + Hash>(
+    parents: &HashMap<N, N>,
+    a: N,
+    b: N,
+) -> Option<N> {
+    let mut seen: HashSet<N> = ancestors(parents, a.clone()).into_iter().collect();
+    seen.insert(a);
+    std::iter::once(b.clone())
+        .chain(ancestors(parents, b))
+        .find(|n| seen.contains(n))
+}
+
+/// A directed graph with weighted edges, stored as adjacency lists.
+#[derive(Debug, Clone)]
+pub struct Graph<N> {
+    edges: HashMap<N, Vec<(N, usize)>>,
+}
+
+impl<N> Default for Graph<N> {
+    fn default() -> Self {
+        Self {
+            edges: HashMap::new(),
+        }
+    }
+}
+
+impl<N: Copy + Eq + Hash> Graph<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.edges.entry(node).or_default();
+    }
+
+    /// Adds an edge from `from` to `to`; both nodes are added if necessary.
+    pub fn add_edge(&mut self, from: N, to: N, weight: usize) {
+        self.add_node(to);
+        self.edges.entry(from).or_default().push((to, weight));
+    }
+
+    /// Adds an edge in both directions.
+    pub fn add_undirected_edge(&mut self, a: N, b: N, weight: usize) {
+        self.add_edge(a, b, weight);
+        self.add_edge(b, a, weight);
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    pub fn contains(&self, node: N) -> bool {
+        self.edges.contains_key(&node)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        self.edges.keys().copied()
+    }
+
+    /// The outgoing edges of `node` and their weights.
+    pub fn edges(&self, node: N) -> &[(N, usize)] {
+        self.edges.get(&node).map_or(&[], |e| &e[..])
+    }
+
+    pub fn neighbours(&self, node: N) -> impl Iterator<Item = N> + '_ {
+        self.edges(node).iter().map(|&(n, _)| n)
+    }
+
+    /// Number of edges needed to reach every reachable node, ignoring the weights.
+    pub fn bfs(&self, start: N) -> HashMap<N, usize> {
+        bfs(start, |n| self.neighbours(n))
+    }
+}
+
+impl<N: Copy + Ord + Hash> Graph<N> {
+    pub fn dijkstra(&self, start: N) -> HashMap<N, usize> {
+        dijkstra(start, |n| self.edges(n).iter().copied())
+    }
+
+    pub fn shortest_path(&self, start: N, goal: N) -> Option<Path<N>> {
+        shortest_path(start, |n| n == goal, |n| self.edges(n).iter().copied())
+    }
+
+    pub fn astar<H: FnMut(N) -> usize>(&self, start: N, goal: N, heuristic: H) -> Option<Path<N>> {
+        astar(
+            start,
+            |n| n == goal,
+            |n| self.edges(n).iter().copied(),
+            heuristic,
+        )
+    }
+
+    pub fn all_pairs(&self) -> HashMap<(N, N), usize> {
+        let nodes: Vec<N> = self.nodes().collect();
+        all_pairs(&nodes, |n| self.edges(n).iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //   a --1-- b --1-- c
+    //    \             /
+    //     ------5------
+    fn triangle() -> Graph<char> {
+        let mut graph = Graph::new();
+        graph.add_undirected_edge('a', 'b', 1);
+        graph.add_undirected_edge('b', 'c', 1);
+        graph.add_undirected_edge('a', 'c', 5);
+        graph.add_node('d');
+        graph
+    }
+
+    #[test]
+    fn test_bfs() {
+        let graph = triangle();
+        let distances = graph.bfs('a');
+        assert_eq!(3, distances.len());
+        assert_eq!(1, distances[&'c']);
+        assert_eq!(
+            vec!['a', 'c'],
+            path_to(&bfs_tree('a', |n| graph.neighbours(n)), 'c')
+        );
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let graph = triangle();
+        let distances = graph.dijkstra('a');
+        assert_eq!(2, distances[&'c']);
+        assert_eq!(None, distances.get(&'d'));
+        assert_eq!(
+            Some(Path {
+                cost: 2,
+                nodes: vec!['a', 'b', 'c']
+            }),
+            graph.shortest_path('a', 'c')
+        );
+        assert_eq!(None, graph.shortest_path('a', 'd'));
+    }
+
+    #[test]
+    fn test_astar() {
+        // walk on an open 10x10 field with the manhattan distance as heuristic
+        let mut expanded = 0;
+        let path = astar(
+            (0i64, 0i64),
+            |p| p == (9, 9),
+            |(x, y)| {
+                expanded += 1;
+                vec![(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                    .into_iter()
+                    .filter(|&(x, y)| (0..10).contains(&x) && (0..10).contains(&y))
+                    .map(|p| (p, 1))
+                    .collect::<Vec<_>>()
+            },
+            |(x, y)| (18 - x - y) as usize,
+        )
+        .unwrap();
+        assert_eq!(18, path.cost);
+        assert_eq!(19, path.nodes.len());
+        assert!(expanded < 100);
+    }
+
+    #[test]
+    fn test_all_pairs() {
+        let distances = triangle().all_pairs();
+        assert_eq!(2, distances[&('c', 'a')]);
+        assert_eq!(0, distances[&('b', 'b')]);
+        assert_eq!(None, distances.get(&('a', 'd')));
+    }
+
+    #[test]
+    fn test_lowest_common_ancestor() {
+        //     r
+        //    / \
+        //   a   b
+        //  / \
+        // c   d
+        let parents: HashMap<char, char> = [('a', 'r'), ('b', 'r'), ('c', 'a'), ('d', 'a')]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(vec!['a', 'r'], ancestors(&parents, 'c'));
+        assert_eq!(Some('a'), lowest_common_ancestor(&parents, 'c', 'd'));
+        assert_eq!(Some('r'), lowest_common_ancestor(&parents, 'c', 'b'));
+        assert_eq!(Some('a'), lowest_common_ancestor(&parents, 'a', 'd'));
+        assert_eq!(None, lowest_common_ancestor(&parents, 'c', 'x'));
+    }
+
+    #[test]
+    fn test_ancestors_cycle() {
+        // a -> b -> c -> a, with d hanging off the cycle
+        let parents: HashMap<char, char> = [('a', 'b'), ('b', 'c'), ('c', 'a'), ('d', 'a')]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(vec!['b', 'c'], ancestors(&parents, 'a'));
+        assert_eq!(vec!['a', 'b', 'c'], ancestors(&parents, 'd'));
+        assert_eq!(Some('c'), lowest_common_ancestor(&parents, 'd', 'c'));
+        assert_eq!(None, lowest_common_ancestor(&parents, 'd', 'x'));
+    }
+}
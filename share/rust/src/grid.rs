@@ -0,0 +1,485 @@
+//! Two-dimensional grids: a dense `Grid<T>` for puzzle inputs of a known size and a
+//! `SparseGrid<T>` for unbounded areas which are discovered step by step.
+//!
+//! The x axis points east, the y axis points south, i.e. `(0, 0)` is the top-left corner.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Sub};
+
+use crate::graph::{bfs, dijkstra};
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub const NORTH: Point = Point { x: 0, y: -1 };
+    pub const SOUTH: Point = Point { x: 0, y: 1 };
+    pub const EAST: Point = Point { x: 1, y: 0 };
+    pub const WEST: Point = Point { x: -1, y: 0 };
+
+    pub fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    /// The points to the north, east, south and west (in this order).
+    pub fn neighbours4(self) -> [Point; 4] {
+        [
+            self + Point::NORTH,
+            self + Point::EAST,
+            self + Point::SOUTH,
+            self + Point::WEST,
+        ]
+    }
+
+    /// Like `neighbours4`, but including the diagonals.
+    pub fn neighbours8(self) -> [Point; 8] {
+        let Point { x, y } = self;
+        [
+            Point::new(x, y - 1),
+            Point::new(x + 1, y - 1),
+            Point::new(x + 1, y),
+            Point::new(x + 1, y + 1),
+            Point::new(x, y + 1),
+            Point::new(x - 1, y + 1),
+            Point::new(x - 1, y),
+            Point::new(x - 1, y - 1),
+        ]
+    }
+
+    pub fn manhattan(self, other: Point) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Self {
+        Point::new(x, y)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// A dense grid of `width * height` cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            cells: vec![value; width * height],
+            width,
+            height,
+        }
+    }
+
+    /// Parses an ASCII picture, mapping every character with `f`. Lines shorter than the
+    /// longest one are padded with spaces; a trailing newline is ignored.
+    pub fn parse<F>(s: &str, mut f: F) -> Self
+    where
+        F: FnMut(Point, char) -> T,
+    {
+        let lines: Vec<&str> = s.lines().collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            let mut chars = line.chars();
+            for x in 0..width {
+                let c = chars.next().unwrap_or(' ');
+                cells.push(f(Point::new(x as i64, y as i64), c));
+            }
+        }
+        Self {
+            cells,
+            width,
+            height: lines.len(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    fn index_of(&self, p: Point) -> Option<usize> {
+        if self.contains(p) {
+            Some(p.y as usize * self.width + p.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index_of(p).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index_of(p).map(move |i| &mut self.cells[i])
+    }
+
+    /// Panics if `p` is outside of the grid.
+    pub fn set(&mut self, p: Point, value: T) {
+        self[p] = value;
+    }
+
+    /// All points of the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width as i64;
+        (0..self.cells.len() as i64).map(move |i| Point::new(i % width, i / width))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The first point (row by row) whose cell matches `pred`.
+    pub fn find<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Option<Point> {
+        self.iter().find(|(_, t)| pred(t)).map(|(p, _)| p)
+    }
+
+    /// All points whose cell matches `pred`.
+    pub fn positions<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Vec<Point> {
+        self.iter()
+            .filter(|(_, t)| pred(t))
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    /// The neighbours to the north, east, south and west which are inside the grid.
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        IntoIterator::into_iter(p.neighbours4()).filter(move |&n| self.contains(n))
+    }
+
+    /// Like `neighbours4`, but including the diagonals.
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        IntoIterator::into_iter(p.neighbours8()).filter(move |&n| self.contains(n))
+    }
+
+    /// Distance of every point which can be reached from `start` by walking north, east,
+    /// south or west onto cells for which `passable` holds.
+    pub fn bfs<F>(&self, start: Point, mut passable: F) -> HashMap<Point, usize>
+    where
+        F: FnMut(Point, &T) -> bool,
+    {
+        bfs(start, |p| {
+            self.neighbours4(p)
+                .filter(|&n| passable(n, &self[n]))
+                .collect::<Vec<Point>>()
+        })
+    }
+
+    /// Like `bfs`, but entering a cell costs `cost(point, cell)`; `None` means impassable.
+    pub fn dijkstra<F>(&self, start: Point, mut cost: F) -> HashMap<Point, usize>
+    where
+        F: FnMut(Point, &T) -> Option<usize>,
+    {
+        dijkstra(start, |p| {
+            self.neighbours4(p)
+                .filter_map(|n| cost(n, &self[n]).map(|c| (n, c)))
+                .collect::<Vec<(Point, usize)>>()
+        })
+    }
+
+    /// All points connected to `start` via cells for which `passable` holds.
+    pub fn flood_fill<F>(&self, start: Point, passable: F) -> HashSet<Point>
+    where
+        F: FnMut(Point, &T) -> bool,
+    {
+        self.bfs(start, passable).into_keys().collect()
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Draws the grid, mapping every cell to a character with `f`.
+    pub fn render<F: FnMut(&T) -> char>(&self, mut f: F) -> String {
+        let mut result = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            result.extend(row.iter().map(&mut f));
+            result.push('\n');
+        }
+        result
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        match self.index_of(p) {
+            Some(i) => &self.cells[i],
+            None => panic!("{} is outside of the grid", p),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        match self.index_of(p) {
+            Some(i) => &mut self.cells[i],
+            None => panic!("{} is outside of the grid", p),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A grid without bounds; only the cells which were set take up memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.cells.contains_key(&p)
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.cells.get(&p)
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.cells.get_mut(&p)
+    }
+
+    /// Returns the cell at `p`, inserting `default()` first if it is not set yet.
+    pub fn entry<F: FnOnce() -> T>(&mut self, p: Point, default: F) -> &mut T {
+        self.cells.entry(p).or_insert_with(default)
+    }
+
+    /// Returns the previous value of the cell, if any.
+    pub fn set(&mut self, p: Point, value: T) -> Option<T> {
+        self.cells.insert(p, value)
+    }
+
+    pub fn remove(&mut self, p: Point) -> Option<T> {
+        self.cells.remove(&p)
+    }
+
+    /// The cells which were set, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(&p, t)| (p, t))
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells.keys().copied()
+    }
+
+    /// The top-left and the bottom-right corner of the smallest rectangle containing every
+    /// cell which was set.
+    pub fn bounding_box(&self) -> Option<(Point, Point)> {
+        let mut points = self.cells.keys();
+        let first = *points.next()?;
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        }))
+    }
+
+    /// Distance of every set cell which can be reached from `start` by walking north, east,
+    /// south or west onto set cells for which `passable` holds.
+    pub fn bfs<F>(&self, start: Point, mut passable: F) -> HashMap<Point, usize>
+    where
+        F: FnMut(Point, &T) -> bool,
+    {
+        bfs(start, |p: Point| {
+            IntoIterator::into_iter(p.neighbours4())
+                .filter(|n| self.cells.get(n).is_some_and(|t| passable(*n, t)))
+                .collect::<Vec<Point>>()
+        })
+    }
+
+    /// Like `bfs`, but entering a cell costs `cost(point, cell)`; `None` means impassable.
+    pub fn dijkstra<F>(&self, start: Point, mut cost: F) -> HashMap<Point, usize>
+    where
+        F: FnMut(Point, &T) -> Option<usize>,
+    {
+        dijkstra(start, |p: Point| {
+            IntoIterator::into_iter(p.neighbours4())
+                .filter_map(|n| self.cells.get(&n).and_then(|t| cost(n, t)).map(|c| (n, c)))
+                .collect::<Vec<(Point, usize)>>()
+        })
+    }
+
+    /// All set cells connected to `start` via cells for which `passable` holds.
+    pub fn flood_fill<F>(&self, start: Point, passable: F) -> HashSet<Point>
+    where
+        F: FnMut(Point, &T) -> bool,
+    {
+        self.bfs(start, passable).into_keys().collect()
+    }
+
+    /// Copies the bounding box into a dense grid; cells which are not set become `None`.
+    /// Returns the grid and the position of its top-left corner.
+    pub fn to_dense(&self) -> (Grid<Option<&T>>, Point) {
+        let (min, max) = match self.bounding_box() {
+            Some(bbox) => bbox,
+            None => return (Grid::new(0, 0, None), Point::default()),
+        };
+        let width = (max.x - min.x + 1) as usize;
+        let height = (max.y - min.y + 1) as usize;
+        let mut grid = Grid::new(width, height, None);
+        for (p, t) in self.iter() {
+            grid[p - min] = Some(t);
+        }
+        (grid, min)
+    }
+
+    /// Draws the bounding box, mapping every cell to a character with `f`.
+    pub fn render<F: FnMut(Option<&T>) -> char>(&self, mut f: F) -> String {
+        self.to_dense().0.render(|t| f(*t))
+    }
+}
+
+impl<T> std::iter::FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> Self {
+        Self {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: &str = "#####\n#..##\n#.#.#\n#...\n";
+
+    #[test]
+    fn test_parse_and_render() {
+        let grid = Grid::parse(MAZE, |_, c| c == '#');
+        assert_eq!(5, grid.width());
+        assert_eq!(4, grid.height());
+        // the short last line is padded
+        assert_eq!(Some(&false), grid.get(Point::new(4, 3)));
+        assert_eq!(None, grid.get(Point::new(5, 0)));
+        assert_eq!(
+            "#####\n#..##\n#.#.#\n#....\n",
+            grid.render(|&wall| if wall { '#' } else { '.' })
+        );
+        assert_eq!(Some(Point::new(1, 1)), grid.find(|&wall| !wall));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(3, 3, 0);
+        assert_eq!(2, grid.neighbours4(Point::new(0, 0)).count());
+        assert_eq!(4, grid.neighbours4(Point::new(1, 1)).count());
+        assert_eq!(3, grid.neighbours8(Point::new(2, 2)).count());
+        assert_eq!(8, grid.neighbours8(Point::new(1, 1)).count());
+    }
+
+    #[test]
+    fn test_bfs() {
+        let grid = Grid::parse(MAZE, |_, c| c);
+        let distances = grid.bfs(Point::new(1, 1), |_, &c| c != '#');
+        assert_eq!(Some(&0), distances.get(&Point::new(1, 1)));
+        assert_eq!(Some(&4), distances.get(&Point::new(3, 3)));
+        assert_eq!(None, distances.get(&Point::new(3, 1)));
+        assert_eq!(8, grid.flood_fill(Point::new(1, 1), |_, &c| c != '#').len());
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let grid = Grid::parse("19\n11\n", |_, c| c.to_digit(10).unwrap() as usize);
+        let distances = grid.dijkstra(Point::new(0, 0), |_, &cost| Some(cost));
+        assert_eq!(2, distances[&Point::new(1, 1)]);
+        // entering a cell always costs the same, no matter where we come from
+        assert_eq!(9, distances[&Point::new(1, 0)]);
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(None, grid.bounding_box());
+        grid.set(Point::new(-1, 2), 'a');
+        grid.set(Point::new(1, 0), 'b');
+        grid.set(Point::new(0, 2), 'c');
+        assert_eq!(
+            Some((Point::new(-1, 0), Point::new(1, 2))),
+            grid.bounding_box()
+        );
+        assert_eq!("  b\n   \nac \n", grid.render(|c| *c.unwrap_or(&' ')));
+        let distances = grid.bfs(Point::new(-1, 2), |_, _| true);
+        assert_eq!(2, distances.len());
+    }
+}
@@ -73,6 +73,11 @@ impl IntcodeProgram {
     }
 
     pub fn run(&mut self, input: &[i64], output: &mut Vec<i64>) -> IntcodeResult {
+        self.run_with(input, |n| output.push(n))
+    }
+
+    /// Like `run`, but hands every output number to `sink` as soon as it is produced.
+    pub fn run_with<F: FnMut(i64)>(&mut self, input: &[i64], mut sink: F) -> IntcodeResult {
         let mut number_idx = 0;
 
         let n = self.code.len();
@@ -136,7 +141,7 @@ impl IntcodeProgram {
                 4 => {
                     let a = self.read_param(&param_modes, 0);
                     trace!("[Put] Appending {}", a);
-                    output.push(a);
+                    sink(a);
                     self.ip += 2;
                 }
                 // jump-if-true
@@ -464,6 +469,17 @@ mod tests {
         assert_eq!(output, code);
     }
 
+    #[test]
+    fn test_run_with() {
+        let code = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+        let mut program = IntcodeProgram::new(code.clone());
+        let mut output = Vec::new();
+        program.run_with(&[], |n| output.push(n));
+        assert_eq!(output, code);
+    }
+
     #[test]
     fn test_opcode9() {
         let code = vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0];
@@ -1 +1,4 @@
+pub mod graph;
+pub mod grid;
 pub mod intcode;
+pub mod ocr;
@@ -0,0 +1,131 @@
+//! Reading the block letters which some puzzles draw as their answer.
+//!
+//! The letters are 6 pixels high and (mostly) 4 pixels wide, separated by blank columns. Any
+//! number of blank rows and columns may surround them.
+
+use crate::grid::{Grid, Point};
+
+/// The known letters, with `#` for set pixels.
+const LETTERS: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', ["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Reads the letters drawn with `true` pixels. Fails on the first glyph which is not a known
+/// letter, showing what it looks like.
+pub fn read(pixels: &Grid<bool>) -> Result<String, String> {
+    let set = |x: usize, y: usize| pixels[Point::new(x as i64, y as i64)];
+    let blank_row = |y: usize| (0..pixels.width()).all(|x| !set(x, y));
+    let blank_column = |x: usize| (0..pixels.height()).all(|y| !set(x, y));
+
+    let top = match (0..pixels.height()).find(|&y| !blank_row(y)) {
+        Some(top) => top,
+        None => return Ok(String::new()),
+    };
+    let bottom = (0..pixels.height()).rev().find(|&y| !blank_row(y)).unwrap();
+
+    let mut result = String::new();
+    let mut x = 0;
+    while x < pixels.width() {
+        if blank_column(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < pixels.width() && !blank_column(x) {
+            x += 1;
+        }
+        let glyph: Vec<String> = (top..=bottom)
+            .map(|y| {
+                (start..x)
+                    .map(|x| if set(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        match LETTERS
+            .iter()
+            .find(|(_, letter)| letter.iter().eq(glyph.iter()))
+        {
+            Some(&(c, _)) => result.push(c),
+            None => {
+                return Err(format!(
+                    "Unrecognized glyph at column {}:\n{}",
+                    start,
+                    glyph.join("\n")
+                ))
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Grid<bool> {
+        Grid::parse(s, |_, c| c == '#')
+    }
+
+    #[test]
+    fn test_read() {
+        let text = "
+ ##  #### ###  #  # ####   ##  ##  ###
+#  # #    #  # # #     #    # #  # #  #
+#    ###  #  # ##     #     # #    #  #
+#    #    ###  # #   #      # #    ###
+#  # #    #    # #  #    #  # #  # # #
+ ##  #### #    #  # ####  ##   ##  #  #
+";
+        assert_eq!(Ok("CEPKZJCR".to_string()), read(&parse(text)));
+    }
+
+    #[test]
+    fn test_padding() {
+        // blank rows and columns around the letters, and uneven gaps between them
+        let text = "
+..........................
+...###...#...#..####......
+....#....#...#..#.........
+....#.....#.#...###.......
+....#......#....#.........
+....#......#....#.........
+...###.....#....####......
+..........................
+";
+        assert_eq!(Ok("IYE".to_string()), read(&parse(text)));
+        assert_eq!(Ok(String::new()), read(&parse("...\n...")));
+    }
+
+    #[test]
+    fn test_unrecognized() {
+        let text = "
+#..#.#.#
+#..#.#.#
+####..#.
+#..#.#.#
+#..#.#.#
+#..#.#.#
+";
+        assert_eq!(
+            Err("Unrecognized glyph at column 5:\n#.#\n#.#\n.#.\n#.#\n#.#\n#.#".to_string()),
+            read(&parse(text))
+        );
+    }
+}